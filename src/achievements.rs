@@ -0,0 +1,188 @@
+use crate::storage;
+use crate::world::WorldEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The name of the achievements' save file, in the data directory.
+const SAVE_FILE: &str = "achievements.json";
+
+/// The version of the achievements' save file written by this version of the game.
+const SAVE_VERSION: u32 = 1;
+
+/// The Achievement struct describes an achievement, as shown on the achievements screen.
+pub struct Achievement {
+    /// The id field is the key under which the achievement is saved, it must never change.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// The ACHIEVEMENTS constant lists every achievement of the game, in the order of the achievements screen.
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "first_landing",
+        name: "Touchdown",
+        description: "Land on a landing pad",
+    },
+    Achievement {
+        id: "soft_landing",
+        name: "Feather touch",
+        description: "Land with under 0.1 vertical speed",
+    },
+    Achievement {
+        id: "score_10",
+        name: "Rover cadet",
+        description: "Reach a score of 10, near misses aside",
+    },
+    Achievement {
+        id: "score_25",
+        name: "Asteroid dodger",
+        description: "Reach a score of 25, near misses aside",
+    },
+    Achievement {
+        id: "score_50",
+        name: "Veteran pilot",
+        description: "Reach a score of 50, near misses aside",
+    },
+    Achievement {
+        id: "no_left_50",
+        name: "Right-minded",
+        description: "Survive 50 asteroids without thrusting left",
+    },
+    Achievement {
+        id: "near_miss_10",
+        name: "Daredevil",
+        description: "Get 10 near misses in a single game",
+    },
+    Achievement {
+        id: "avoided_100",
+        name: "Centurion",
+        description: "Avoid 100 asteroids in a single game",
+    },
+];
+
+/// The RunProgress struct counts what the achievements need to know about the current game.
+#[derive(Default)]
+struct RunProgress {
+    avoided: usize,
+    avoided_without_left: usize,
+    near_misses: usize,
+}
+
+/// The Achievements struct keeps track of the unlocked achievements, and of the progress made towards the other
+/// ones during the current game. The unlocked achievements are saved in the data directory.
+#[derive(Serialize, Deserialize)]
+pub struct Achievements {
+    pub version: u32,
+    /// The unlocked field maps the id of each unlocked achievement to the time it was unlocked, in seconds since
+    /// the unix epoch.
+    pub unlocked: BTreeMap<String, u64>,
+    #[serde(skip)]
+    run: RunProgress,
+}
+
+impl Default for Achievements {
+    fn default() -> Self {
+        Achievements {
+            version: SAVE_VERSION,
+            unlocked: BTreeMap::new(),
+            run: RunProgress::default(),
+        }
+    }
+}
+
+impl Achievements {
+    /// The load function reads the unlocked achievements from the data directory. A file written by a newer version
+    /// of the game is ignored rather than misread.
+    pub fn load() -> Self {
+        let achievements: Achievements = storage::load(SAVE_FILE);
+        if achievements.version > SAVE_VERSION {
+            eprintln!(
+                "Ignoring {}: version {} is not supported",
+                SAVE_FILE, achievements.version
+            );
+            return Achievements::default();
+        }
+        achievements
+    }
+
+    /// The save function writes the unlocked achievements to the data directory.
+    pub fn save(&self) {
+        if let Err(error) = storage::save(SAVE_FILE, self) {
+            eprintln!("Could not save the achievements: {}", error);
+        }
+    }
+
+    /// The start_run function resets the progress made during the previous game.
+    pub fn start_run(&mut self) {
+        self.run = RunProgress::default();
+    }
+
+    /// The is_unlocked function returns true if the achievement with the given id was unlocked.
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains_key(id)
+    }
+
+    /// The observe function updates the progress of the current game with an event of the player's ship (ship 0),
+    /// and returns the achievements it unlocked.
+    pub fn observe(&mut self, event: &WorldEvent) -> Vec<&'static Achievement> {
+        let mut unlocked = Vec::new();
+        match event {
+            WorldEvent::Landed {
+                ship: 0,
+                vertical_speed,
+                ..
+            } => {
+                self.unlock("first_landing", &mut unlocked);
+                if vertical_speed.abs() < 0.1 {
+                    self.unlock("soft_landing", &mut unlocked);
+                }
+            }
+            WorldEvent::Thrust { ship: 0, input } if input.left => {
+                self.run.avoided_without_left = 0;
+            }
+            WorldEvent::Avoided { .. } => {
+                self.run.avoided += 1;
+                self.run.avoided_without_left += 1;
+                if self.run.avoided >= 100 {
+                    self.unlock("avoided_100", &mut unlocked);
+                }
+                if self.run.avoided_without_left >= 50 {
+                    self.unlock("no_left_50", &mut unlocked);
+                }
+            }
+            WorldEvent::Scored { progress, .. } => {
+                for (threshold, id) in [(10, "score_10"), (25, "score_25"), (50, "score_50")] {
+                    if *progress >= threshold {
+                        self.unlock(id, &mut unlocked);
+                    }
+                }
+            }
+            WorldEvent::NearMiss { ship: 0, .. } => {
+                self.run.near_misses += 1;
+                if self.run.near_misses >= 10 {
+                    self.unlock("near_miss_10", &mut unlocked);
+                }
+            }
+            _ => {}
+        }
+        unlocked
+    }
+
+    /// The unlock function unlocks the achievement with the given id, unless it already was.
+    fn unlock(&mut self, id: &str, unlocked: &mut Vec<&'static Achievement>) {
+        if self.is_unlocked(id) {
+            return;
+        }
+        let Some(achievement) = ACHIEVEMENTS.iter().find(|achievement| achievement.id == id) else {
+            return;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        self.unlocked.insert(id.to_string(), now);
+        unlocked.push(achievement);
+    }
+}
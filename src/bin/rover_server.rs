@@ -0,0 +1,26 @@
+use game_project::level::Level;
+use game_project::net;
+use std::path::Path;
+
+/// The multiplayer server's entry point, it runs the authoritative simulation without opening a window.
+/// The address to listen on can be given as the first argument, it defaults to net::DEFAULT_ADDRESS, and
+/// `--level <path>` makes every round use the given level file.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut address = net::DEFAULT_ADDRESS.to_string();
+    let mut level = Level::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--level" => {
+                let path = args.next().ok_or("--level expects the path of a level file")?;
+                level = Level::load(Path::new(&path))?;
+            }
+            _ => address = arg,
+        }
+    }
+
+    let mut server = net::Server::bind(&address, level)?;
+    println!("Rust Rover server listening on {}", address);
+    server.run()
+}
@@ -0,0 +1,209 @@
+use bracket_lib::prelude::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// The Action enum lists what the keys of the game do, every action is bound to a single key. The same key can be bound
+/// to actions of different screens, such as P starting a game from the main menu and pausing it while playing.
+///
+/// # Variants
+/// - Thrust, Left, Right: fire the ship's thrusters, held down while playing
+/// - Fire: shoots straight up, held down while playing
+/// - Play: starts a game from the main menu, or starts it again from the results screen
+/// - Pause: pauses the game, or resumes it once paused
+/// - Quit: quits the game
+/// - Back: goes back to the previous screen, closes a submenu, resumes the game or answers no to a confirmation
+/// - Blaster, TimeAttack, Daily, Sandbox: start the other game modes from the main menu
+/// - Editor, Achievements, Statistics, Options: open the other screens from the main menu
+/// - Export: exports the daily challenge's result from the results screen
+/// - TestPlay: goes back to the editor from a test play
+/// - ReducedMotion, Language: toggle the reduced motion and choose the language on the options screen
+/// - MenuUp, MenuDown, Select: move the selection of the menus and choose the selected item
+/// - Restart, Controls, MainMenu: restart the game, list the controls and go back to the main menu from the pause menu
+/// - Confirm: answers yes to a confirmation
+/// - Continue: continues the saved run from the main menu
+/// - PadsTool, TerrainTool, SpawnsTool: choose the tool of the level editor
+/// - Save: saves the level being edited
+/// - Win, Gravity: cycle through the win conditions and the planets of the level being edited
+/// - RandomAsteroids, Pickups, Weapons, Craters: toggle the settings of the level being edited
+/// - Smaller, Larger: change the width of the selected pad or the size of the selected spawn in the editor
+/// - Motion: cycles through the motions of the selected pad in the editor
+/// - Remove: removes the selected pad or spawn in the editor
+/// - TurnLeft, TurnRight, Faster, Slower: change the heading and the speed of the selected spawn in the editor
+/// - Later, Earlier: change the tick of the selected spawn in the editor
+/// - Help: shows or hides the help of the editor, drawn over the top of the arena
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Thrust,
+    Left,
+    Right,
+    Fire,
+    Play,
+    Pause,
+    Quit,
+    Back,
+    Blaster,
+    TimeAttack,
+    Daily,
+    Sandbox,
+    Editor,
+    Achievements,
+    Statistics,
+    Options,
+    Export,
+    TestPlay,
+    ReducedMotion,
+    Language,
+    MenuUp,
+    MenuDown,
+    Select,
+    Restart,
+    Controls,
+    MainMenu,
+    Confirm,
+    Continue,
+    PadsTool,
+    TerrainTool,
+    SpawnsTool,
+    Save,
+    Win,
+    Gravity,
+    RandomAsteroids,
+    Pickups,
+    Weapons,
+    Craters,
+    Smaller,
+    Larger,
+    Motion,
+    Remove,
+    TurnLeft,
+    TurnRight,
+    Faster,
+    Slower,
+    Later,
+    Earlier,
+    Help,
+}
+
+/// The keys a binding can be set to, they are named in the options' save file the way bracket-lib names them.
+const KEYS: [VirtualKeyCode; 58] = {
+    use VirtualKeyCode::*;
+    [
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F2, F3, F4, F5, Up, Down, Left,
+        Right, Back, Return, Space, Tab, Delete, Minus, Equals, LBracket, RBracket, PageUp,
+        PageDown, Home,
+    ]
+};
+
+/// The Bindings struct maps every action to the key triggering it. The keys are the default ones unless the options
+/// rebind some of them.
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    keys: BTreeMap<Action, VirtualKeyCode>,
+}
+
+impl Default for Bindings {
+    /// The default bindings are the game's original keys.
+    fn default() -> Self {
+        use VirtualKeyCode as Key;
+        let keys = [
+            (Action::Thrust, Key::Up),
+            (Action::Left, Key::Left),
+            (Action::Right, Key::Right),
+            (Action::Fire, Key::Space),
+            (Action::Play, Key::P),
+            (Action::Pause, Key::P),
+            (Action::Quit, Key::Q),
+            (Action::Back, Key::Escape),
+            (Action::Blaster, Key::B),
+            (Action::TimeAttack, Key::T),
+            (Action::Daily, Key::D),
+            (Action::Sandbox, Key::O),
+            (Action::Editor, Key::E),
+            (Action::Achievements, Key::A),
+            (Action::Statistics, Key::S),
+            (Action::Options, Key::C),
+            (Action::Export, Key::X),
+            (Action::TestPlay, Key::T),
+            (Action::ReducedMotion, Key::M),
+            (Action::Language, Key::L),
+            (Action::MenuUp, Key::Up),
+            (Action::MenuDown, Key::Down),
+            (Action::Select, Key::Return),
+            (Action::Restart, Key::R),
+            (Action::Controls, Key::K),
+            (Action::MainMenu, Key::M),
+            (Action::Confirm, Key::Y),
+            (Action::Continue, Key::N),
+            (Action::PadsTool, Key::Key1),
+            (Action::TerrainTool, Key::Key2),
+            (Action::SpawnsTool, Key::Key3),
+            (Action::Save, Key::S),
+            (Action::Win, Key::W),
+            (Action::Gravity, Key::G),
+            (Action::RandomAsteroids, Key::R),
+            (Action::Pickups, Key::P),
+            (Action::Weapons, Key::F),
+            (Action::Craters, Key::C),
+            (Action::Smaller, Key::LBracket),
+            (Action::Larger, Key::RBracket),
+            (Action::Motion, Key::M),
+            (Action::Remove, Key::Delete),
+            (Action::TurnLeft, Key::Left),
+            (Action::TurnRight, Key::Right),
+            (Action::Faster, Key::Up),
+            (Action::Slower, Key::Down),
+            (Action::Later, Key::PageUp),
+            (Action::Earlier, Key::PageDown),
+            (Action::Help, Key::H),
+        ];
+        Bindings {
+            keys: keys.into_iter().collect(),
+        }
+    }
+}
+
+impl Bindings {
+    /// The new function returns the default bindings with the given actions rebound, each to the key of the given
+    /// name. A key name that is not known is reported on stderr and the action keeps its default key.
+    pub fn new(rebound: &BTreeMap<Action, String>) -> Self {
+        let mut bindings = Bindings::default();
+        for (action, name) in rebound {
+            match key_from_name(name) {
+                Some(key) => {
+                    bindings.keys.insert(*action, key);
+                }
+                None => eprintln!(
+                    "Ignoring the binding of {:?}: unknown key {:?}",
+                    action, name
+                ),
+            }
+        }
+        bindings
+    }
+
+    /// The key function returns the key bound to the given action.
+    pub fn key(&self, action: Action) -> VirtualKeyCode {
+        self.keys[&action]
+    }
+
+    /// The is function tells whether the given key, usually the one pressed this frame, triggers the given action.
+    pub fn is(&self, key: VirtualKeyCode, action: Action) -> bool {
+        self.key(action) == key
+    }
+
+    /// The held function tells whether the key bound to the given action is among the pressed keys.
+    pub fn held(&self, keys: &HashSet<VirtualKeyCode>, action: Action) -> bool {
+        keys.contains(&self.key(action))
+    }
+}
+
+/// The key_from_name function returns the key of the given name, such as "P", "Space" or "Key1".
+pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    KEYS.into_iter().find(|key| format!("{:?}", key) == name)
+}
+
+/// The key_name function returns the name of the given key, as it is written in the options' save file.
+pub fn key_name(key: VirtualKeyCode) -> String {
+    format!("{:?}", key)
+}
@@ -1,5 +1,7 @@
-use std::cmp::Ordering;
 use bracket_lib::prelude::*;
+use crate::environment::{Environment, Vector};
+use crate::theme::Theme;
+use serde::{Deserialize, Serialize};
 
 /// The Direction enum is used to represent the possible directions the character can move in.
 /// It is mainly used as a parameter to the thrust function of the Character struct.
@@ -9,8 +11,27 @@ pub enum Direction {
     Right,
 }
 
+/// The ShipInput struct is used to represent which thrusters are firing during a frame. It is built from the keyboard
+/// in the game window, and sent over the network by multiplayer clients, so it can be serialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShipInput {
+    /// Up is set when the ship is thrusting up.
+    pub up: bool,
+    /// Left is set when the ship is thrusting left.
+    pub left: bool,
+    /// Right is set when the ship is thrusting right.
+    pub right: bool,
+    /// Fire is set when the ship is firing its weapon, it only shoots in levels with weapons.
+    pub fire: bool,
+}
+
+/// The fastest a ship can fly, whatever the forces pulling it. It keeps a ship slingshot around a gravity well from
+/// crossing the arena in a single tick.
+pub const MAX_SPEED: f32 = 2.0;
+
 /// The Momentum struct is used to calculate the ship's movement.
-#[derive(PartialEq, PartialOrd)]
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
 pub struct Momentum {
     /// Vertical is applied to the y coordinate of the ship.
     pub vertical: f32,
@@ -30,6 +51,7 @@ impl Momentum {
 }
 
 /// The Character struct is used to create and manage the ship that the player will be controlling.
+#[derive(Clone)]
 pub struct Character {
     /// The coordinate field is used to store the position of the ship in floating point coordinates to allow for fancy rendering.
     pub coordinate: PointF,
@@ -39,6 +61,32 @@ pub struct Character {
     scaling: PointF,
     /// The momentum field is used to store a momentum struct that is used to calculate the ship's movement.
     pub momentum: Momentum,
+    /// The force field accumulates the forces applied to the ship during the current tick, they are added to its
+    /// momentum by the apply_momentum function.
+    pub force: Vector,
+    /// The destroyed field is set once the ship collided with an asteroid, a destroyed ship is no longer simulated.
+    pub destroyed: bool,
+    /// The landed field is set while the ship is resting on the landing pad.
+    pub landed: bool,
+    /// The shield field is the number of ticks the ship's shield still lasts, the ship is shielded while it is not 0.
+    pub shield: u64,
+    /// The cooldown field is the number of ticks before the ship's weapon can fire again.
+    pub cooldown: u32,
+    /// The thrusting field is set while the ship fires at least one of its thrusters, to tell when it starts and
+    /// stops thrusting.
+    pub thrusting: bool,
+    /// The combo field is the multiplier applied to the points the ship scores, it goes up with every near miss of the
+    /// ship and drops back by one every COMBO_TICKS without one, down to 1. Getting hit sets it back to 1.
+    pub combo: usize,
+    /// The combo_timer field is the number of ticks before the combo multiplier drops by one.
+    pub combo_timer: u64,
+    /// The target field is the index of the pad the ship has to reach next in a pad sequence, the ship reached every
+    /// pad once it equals their number.
+    pub target: usize,
+    /// The contact field counts the ticks the ship has been resting on its target pad, see SETTLE_TICKS.
+    pub contact: u64,
+    /// The splits field stores the tick at which the ship reached each pad of a pad sequence.
+    pub splits: Vec<u64>,
 }
 
 impl Character {
@@ -49,20 +97,45 @@ impl Character {
             orientation: Degrees::new(0.0),
             scaling: PointF::new(1.0, 1.0),
             momentum: Momentum::new(),
+            force: Vector::default(),
+            destroyed: false,
+            landed: false,
+            shield: 0,
+            cooldown: 0,
+            thrusting: false,
+            combo: 1,
+            combo_timer: 0,
+            target: 0,
+            contact: 0,
+            splits: Vec::new(),
         }
     }
 
     /// The render function is used to draw the ship to the screen. Since it uses floating-point fancy-rendering, it must
     /// first shift on the fancy-console (id 1), before drawing the ship, and then shift back to the main console (id 0).
-    pub fn render(&mut self, ctx: &mut BTerm) {
+    /// The ship is drawn in the colors of the given theme.
+    pub fn render(&mut self, ctx: &mut BTerm, theme: &Theme) {
         ctx.set_active_console(1);
 
+        // A shielded ship is drawn inside a bubble
+        if self.shield > 0 {
+            ctx.set_fancy(
+                self.coordinate,
+                1,
+                Degrees::new(0.0),
+                PointF::new(2.0, 2.0),
+                theme.shield,
+                BLACK,
+                to_cp437('O'),
+            );
+        }
+
         ctx.set_fancy(
             self.coordinate,
             1,
             self.orientation,
             self.scaling,
-            BLUE,
+            theme.ship,
             BLACK,
             to_cp437('^'),
         );
@@ -93,28 +166,69 @@ impl Character {
         }
     }
 
-    /// This function updates the ship's position using the momentum struct.
+    /// The apply_input function fires the thrusters set in the input parameter, using the game's thrust values.
+    pub fn apply_input(&mut self, input: &ShipInput) {
+        if input.up {
+            self.thrust(Direction::Up, 0.42, 1.0);
+        }
+        if input.left {
+            self.thrust(Direction::Left, 0.3, 1.0);
+        }
+        if input.right {
+            self.thrust(Direction::Right, 0.3, 1.0);
+        }
+    }
+
+    /// The apply_force function adds a force to the ones applied to the ship during the current tick.
+    pub fn apply_force(&mut self, force: Vector) {
+        self.force += force;
+    }
+
+    /// This function adds the forces accumulated during the tick to the ship's momentum, within MAX_SPEED, then
+    /// updates the ship's position using the momentum struct.
     pub fn apply_momentum(&mut self) {
+        self.momentum.horizontal += self.force.x;
+        self.momentum.vertical += self.force.y;
+        self.force = Vector::default();
+
+        let speed = Vector {
+            x: self.momentum.horizontal,
+            y: self.momentum.vertical,
+        }
+        .length();
+        if speed > MAX_SPEED {
+            self.momentum.horizontal *= MAX_SPEED / speed;
+            self.momentum.vertical *= MAX_SPEED / speed;
+        }
+
         self.coordinate.x += self.momentum.horizontal;
         self.coordinate.y += self.momentum.vertical;
     }
 
-    /// This function applies gravity and drag to the ship's momentum. It takes in a gravity and a drag parameter.
-    /// The gravity parameter represent's the force pulling the ship down, and the drag parameter allows for a sort of
-    /// inertia in horizontal movement.
-    pub fn apply_gravity_and_drag(&mut self, gravity: f32, drag: f32) {
-        match self.momentum.vertical.partial_cmp(&gravity) {
-            Some(Ordering::Greater) => self.momentum.vertical = gravity,
-            Some(Ordering::Less) => self.momentum.vertical += gravity,
-            _ => {}
-        }
-        match self.momentum.horizontal.partial_cmp(&0.0) {
-            Some(Ordering::Greater) => self.momentum.horizontal -= drag,
-            Some(Ordering::Less) => self.momentum.horizontal += drag,
-            _ => {}
-        }
-        if self.momentum.horizontal > -0.05 && self.momentum.horizontal < 0.05 {
-            self.momentum.horizontal = 0.0;
+    /// This function applies the forces of the environment to the ship: its gravity, and the drag of its atmosphere.
+    /// The vertical part of the gravity pulls the ship down, and the ship never falls faster than it, as if the
+    /// atmosphere held it back; a gravity without a vertical part doesn't limit the fall, as around gravity wells.
+    /// The horizontal part of the gravity pushes the ship sideways. The drag allows for a sort of inertia in horizontal
+    /// movement: it slows the ship down until it moves along with the air, which is carried by the wind parameter.
+    pub fn apply_environment(&mut self, environment: &Environment, wind: f32) {
+        let gravity = environment.gravity;
+        let excess = self.momentum.vertical + self.force.y - gravity.y;
+        let vertical = if gravity.y > 0.0 && excess >= 0.0 {
+            -excess
+        } else {
+            gravity.y
+        };
+
+        // The drag never pushes the ship past the speed of the wind, it would otherwise swing around it
+        let relative = self.momentum.horizontal + self.force.x - wind;
+        let mut horizontal = -relative.signum() * environment.drag.min(relative.abs());
+        if environment.drag > 0.0 && (relative + horizontal).abs() < 0.05 {
+            horizontal = -relative;
         }
+
+        self.apply_force(Vector {
+            x: horizontal + gravity.x,
+            y: vertical,
+        });
     }
 }
@@ -0,0 +1,106 @@
+use crate::character::ShipInput;
+use crate::net::{AsteroidState, PickupState};
+use crate::weapon::Projectile;
+use crate::world::{Arena, World};
+use serde::Serialize;
+use std::io::{self, BufRead, Write};
+
+/// The ShipReport struct describes the controlled ship in a WorldState line.
+#[derive(Serialize)]
+pub struct ShipReport {
+    pub x: f32,
+    pub y: f32,
+    pub vertical: f32,
+    pub horizontal: f32,
+    pub landed: bool,
+    pub destroyed: bool,
+    /// The number of ticks the ship's shield still lasts, 0 when it is not shielded.
+    pub shield: u64,
+}
+
+/// The WorldState struct is the line written to the external controller before each tick.
+#[derive(Serialize)]
+pub struct WorldState {
+    pub tick: u64,
+    pub score: usize,
+    pub ship: ShipReport,
+    pub asteroids: Vec<AsteroidState>,
+    pub pickups: Vec<PickupState>,
+    pub projectiles: Vec<Projectile>,
+    /// The arena field describes the size of the arena, its physics, its ground and its landing pads.
+    pub arena: Arena,
+}
+
+impl WorldState {
+    /// The from_world function describes the first ship of the world and its surroundings.
+    pub fn from_world(world: &World) -> Self {
+        let character = &world.characters[0];
+        WorldState {
+            tick: world.tick,
+            score: world.score,
+            ship: ShipReport {
+                x: character.coordinate.x,
+                y: character.coordinate.y,
+                vertical: character.momentum.vertical,
+                horizontal: character.momentum.horizontal,
+                landed: character.landed,
+                destroyed: character.destroyed,
+                shield: character.shield,
+            },
+            asteroids: world.asteroids.iter().map(AsteroidState::from).collect(),
+            pickups: world.pickups.iter().map(PickupState::from).collect(),
+            projectiles: world.projectiles.clone(),
+            arena: world.arena.clone(),
+        }
+    }
+}
+
+/// The StdioControl struct lets an external program fly the ship through the JSON-lines protocol: before each tick
+/// the world is written as a single JSON line on stdout, and a line such as `{"up":true,"left":false,"right":false}`
+/// is read back from stdin. Missing flags are read as false, so `{}` lets the ship fall.
+pub struct StdioControl {
+    input: io::StdinLock<'static>,
+    output: io::StdoutLock<'static>,
+}
+
+impl StdioControl {
+    /// The new function locks stdin and stdout for the rest of the game, nothing else may print on stdout.
+    pub fn new() -> Self {
+        StdioControl {
+            input: io::stdin().lock(),
+            output: io::stdout().lock(),
+        }
+    }
+
+    /// The report function writes the world as a JSON line, without waiting for an answer. It is used for the
+    /// final line once the game is over.
+    pub fn report(&mut self, world: &World) -> io::Result<()> {
+        serde_json::to_writer(&mut self.output, &WorldState::from_world(world))?;
+        writeln!(self.output)?;
+        self.output.flush()
+    }
+
+    /// The exchange function writes the world and waits for the controller's action. It returns None once stdin
+    /// was closed. A line that can't be read is reported on stderr and treated as no thrust.
+    pub fn exchange(&mut self, world: &World) -> io::Result<Option<ShipInput>> {
+        self.report(world)?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match serde_json::from_str(line.trim()) {
+            Ok(input) => Ok(Some(input)),
+            Err(error) => {
+                eprintln!("Invalid action line {:?}: {}", line.trim(), error);
+                Ok(Some(ShipInput::default()))
+            }
+        }
+    }
+}
+
+impl Default for StdioControl {
+    fn default() -> Self {
+        StdioControl::new()
+    }
+}
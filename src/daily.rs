@@ -0,0 +1,257 @@
+use crate::character::ShipInput;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The name of the daily results' save file, in the data directory.
+const SAVE_FILE: &str = "daily.json";
+
+/// The name of the file the shareable result lines are appended to, in the data directory.
+const SHARE_FILE: &str = "daily_share.txt";
+
+/// The version of the daily results' save file written by this version of the game.
+const SAVE_VERSION: u32 = 1;
+
+/// The FNV-1a offset basis and prime, see Fnv.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The Fnv struct is a 64 bits FNV-1a hash. It is tiny, stable across versions and platforms, and good enough to tell
+/// two days or two replays apart, which is all the daily challenge needs.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(FNV_OFFSET)
+    }
+}
+
+impl Fnv {
+    /// The write function adds the bytes to the hash.
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// The write_input function adds a frame of input to the hash, as a single byte with one bit per action.
+    pub fn write_input(&mut self, input: &ShipInput) {
+        let bits = input.up as u8
+            | (input.left as u8) << 1
+            | (input.right as u8) << 2
+            | (input.fire as u8) << 3;
+        self.write(&[bits]);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The today function returns the current date, in UTC so that every player gets the same challenge at the same time,
+/// formatted as YYYY-MM-DD.
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+
+    // Converts the number of days since 1970-01-01 into a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The seed function derives the seed of the asteroids and the pad of a day's challenge from its date.
+pub fn seed(date: &str) -> u64 {
+    let mut hash = Fnv::default();
+    hash.write(date.as_bytes());
+    hash.finish()
+}
+
+/// The DailyRun struct follows a daily challenge being played: its date, its seed, and the hash of every input sent
+/// so far, which identifies the run.
+pub struct DailyRun {
+    pub date: String,
+    pub seed: u64,
+    pub replay: Fnv,
+    /// The ranked field is set when the run is the first attempt of the day, only that one is recorded.
+    pub ranked: bool,
+}
+
+impl DailyRun {
+    /// The new function starts a run of today's challenge.
+    pub fn new(results: &DailyResults) -> Self {
+        let date = today();
+        DailyRun {
+            seed: seed(&date),
+            ranked: !results.results.contains_key(&date),
+            date,
+            replay: Fnv::default(),
+        }
+    }
+}
+
+/// The DailyResult struct is the recorded attempt of a day.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub seed: u64,
+    pub score: usize,
+    /// The replay field is the hash of the inputs of the attempt, two identical runs have the same hash.
+    pub replay: u64,
+}
+
+impl DailyResult {
+    /// The share_line function formats the result as a single line of text, to be pasted wherever the players compare
+    /// their results.
+    pub fn share_line(&self, date: &str) -> String {
+        format!(
+            "Rust Rover daily {} | seed {:016x} | score {} | replay {:016x}",
+            date, self.seed, self.score, self.replay
+        )
+    }
+}
+
+/// The DailyResults struct keeps the recorded attempt of every day played, by date. It is saved in the data
+/// directory.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyResults {
+    pub version: u32,
+    pub results: BTreeMap<String, DailyResult>,
+}
+
+impl Default for DailyResults {
+    fn default() -> Self {
+        DailyResults {
+            version: SAVE_VERSION,
+            results: BTreeMap::new(),
+        }
+    }
+}
+
+impl DailyResults {
+    /// The load function reads the daily results from the data directory. A file written by a newer version of the
+    /// game is ignored rather than misread.
+    pub fn load() -> Self {
+        let results: DailyResults = storage::load(SAVE_FILE);
+        if results.version > SAVE_VERSION {
+            eprintln!(
+                "Ignoring {}: version {} is not supported",
+                SAVE_FILE, results.version
+            );
+            return DailyResults::default();
+        }
+        results
+    }
+
+    /// The save function writes the daily results to the data directory.
+    pub fn save(&self) {
+        if let Err(error) = storage::save(SAVE_FILE, self) {
+            eprintln!("Could not save the daily results: {}", error);
+        }
+    }
+
+    /// The record function keeps the result of a finished run, unless it is not the first attempt of its day.
+    pub fn record(&mut self, run: &DailyRun, score: usize) {
+        if !run.ranked {
+            return;
+        }
+        self.results.insert(
+            run.date.clone(),
+            DailyResult {
+                seed: run.seed,
+                score,
+                replay: run.replay.finish(),
+            },
+        );
+    }
+
+    /// The export function appends the share line of the given day's result to the share file of the data directory,
+    /// and returns the path of the file.
+    pub fn export(&self, date: &str) -> io::Result<PathBuf> {
+        let result = self.results.get(date).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no result recorded for this day")
+        })?;
+        let path = storage::data_dir().join(SHARE_FILE);
+        std::fs::create_dir_all(storage::data_dir())?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{}", result.share_line(date))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv_matches_the_reference_values() {
+        assert_eq!(Fnv::default().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut hash = Fnv::default();
+        hash.write(b"a");
+        assert_eq!(hash.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn seed_depends_only_on_the_date() {
+        assert_eq!(seed("2026-10-19"), seed("2026-10-19"));
+        assert_ne!(seed("2026-10-19"), seed("2026-10-20"));
+    }
+
+    #[test]
+    fn replay_hash_tells_inputs_apart() {
+        let replay = |inputs: &[ShipInput]| {
+            let mut hash = Fnv::default();
+            inputs.iter().for_each(|input| hash.write_input(input));
+            hash.finish()
+        };
+        let up = ShipInput {
+            up: true,
+            ..ShipInput::default()
+        };
+        let fire = ShipInput {
+            fire: true,
+            ..ShipInput::default()
+        };
+        assert_eq!(replay(&[up, fire]), replay(&[up, fire]));
+        assert_ne!(replay(&[up, fire]), replay(&[fire, up]));
+        assert_ne!(replay(&[up]), replay(&[up, ShipInput::default()]));
+    }
+
+    #[test]
+    fn today_is_a_date() {
+        let date = today();
+        let parts: Vec<&str> = date.split('-').collect();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].len(), 4);
+        assert!((1..=12).contains(&parts[1].parse::<u32>().unwrap()));
+        assert!((1..=31).contains(&parts[2].parse::<u32>().unwrap()));
+    }
+
+    #[test]
+    fn share_line_shows_the_hashes_in_hex() {
+        let result = DailyResult {
+            seed: 0xff,
+            score: 12,
+            replay: 1,
+        };
+        assert_eq!(
+            result.share_line("2026-10-19"),
+            "Rust Rover daily 2026-10-19 | seed 00000000000000ff | score 12 | replay 0000000000000001"
+        );
+    }
+}
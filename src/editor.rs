@@ -0,0 +1,537 @@
+use crate::bindings::{Action, Bindings};
+use crate::environment::Planet;
+use crate::i18n::Catalog;
+use crate::level::{ArenaSize, Level, PadDefinition, SpawnDefinition, WinCondition};
+use crate::map::{LandingPad, PadMotion, Terrain};
+use crate::theme::Theme;
+use bracket_lib::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// The path the editor saves to when it was not opened on a level file.
+pub const DEFAULT_LEVEL_PATH: &str = "levels/custom.ron";
+
+/// The actions of the editor, the keys pressed are looked up among them in this order.
+const ACTIONS: [Action; 23] = [
+    Action::PadsTool,
+    Action::TerrainTool,
+    Action::SpawnsTool,
+    Action::TestPlay,
+    Action::Save,
+    Action::Back,
+    Action::Help,
+    Action::Win,
+    Action::Gravity,
+    Action::RandomAsteroids,
+    Action::Pickups,
+    Action::Weapons,
+    Action::Craters,
+    Action::Smaller,
+    Action::Larger,
+    Action::Motion,
+    Action::Remove,
+    Action::TurnLeft,
+    Action::TurnRight,
+    Action::Faster,
+    Action::Slower,
+    Action::Later,
+    Action::Earlier,
+];
+
+/// The Tool enum lists what the mouse does in the editor.
+///
+/// # Variants
+/// - Pads: Click to place or select a landing pad, drag to move it and set its height
+/// - Terrain: Hold the left button to raise the ground up to the mouse, the right button to flatten it
+/// - Spawns: Click to drop an asteroid spawn marker, or to select an existing one
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tool {
+    Pads,
+    Terrain,
+    Spawns,
+}
+
+/// The EditorAction enum lists what the editor asks the game to do.
+///
+/// # Variants
+/// - TestPlay: Play the level being edited, the game comes back to the editor afterwards
+/// - Exit: Leave the editor and go back to the main menu
+pub enum EditorAction {
+    TestPlay,
+    Exit,
+}
+
+/// The Status enum lists the messages shown under the editor's help.
+///
+/// # Variants
+/// - Editing: The level was opened, the message names the file it is saved to
+/// - Saved: The level was saved to its file
+/// - SaveFailed: The level could not be saved, for the given reason
+/// - TestFailed: The level could not be test played, for the given reason
+enum Status {
+    Editing,
+    Saved,
+    SaveFailed(String),
+    TestFailed(String),
+}
+
+/// The Editor struct holds the level being edited and the state of the editing tools.
+pub struct Editor {
+    /// The level field is the level being edited, it is the one test played and saved.
+    pub level: Level,
+    /// The path field is the file the level is saved to.
+    pub path: PathBuf,
+    tool: Tool,
+    /// The selected field is the index of the selected pad or spawn, depending on the tool.
+    selected: Option<usize>,
+    dragging: bool,
+    /// The spawn field holds the settings given to the next spawn marker dropped.
+    spawn: SpawnDefinition,
+    status: Status,
+    /// The help field is set while the help is shown, it covers the top rows of the arena.
+    help: bool,
+}
+
+impl Editor {
+    /// The open function opens the editor on a level file, a file that does not exist yet starts an empty level.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let level = if path.exists() {
+            Level::load(path)?
+        } else {
+            Level {
+                random_asteroids: false,
+                pickups: false,
+                ..Level::default()
+            }
+        };
+        Ok(Editor {
+            level,
+            path: path.to_path_buf(),
+            tool: Tool::Pads,
+            selected: None,
+            dragging: false,
+            spawn: SpawnDefinition {
+                tick: 60,
+                x: 0.0,
+                y: 0.0,
+                heading: 90.0,
+                speed: 0.4,
+                size: 3.0,
+            },
+            status: Status::Editing,
+            help: true,
+        })
+    }
+
+    /// The update function handles the keys pressed during a frame, in order, through the given bindings, and the mouse,
+    /// and returns what the game should do next.
+    pub fn update(
+        &mut self,
+        ctx: &mut BTerm,
+        keys: &[VirtualKeyCode],
+        bindings: &Bindings,
+    ) -> Option<EditorAction> {
+        let (mouse_x, mouse_y) = ctx.mouse_pos();
+        let (left, right) = {
+            let input = INPUT.lock();
+            (input.is_mouse_button_pressed(0), input.is_mouse_button_pressed(1))
+        };
+
+        for key in keys {
+            let action = ACTIONS
+                .into_iter()
+                .find(|action| bindings.is(*key, *action));
+            match action {
+                Some(Action::PadsTool) => self.select_tool(Tool::Pads),
+                Some(Action::TerrainTool) => self.select_tool(Tool::Terrain),
+                Some(Action::SpawnsTool) => self.select_tool(Tool::Spawns),
+                Some(Action::TestPlay) => match self.level.validate() {
+                    Ok(()) => return Some(EditorAction::TestPlay),
+                    Err(error) => {
+                        self.status = Status::TestFailed(error.to_string());
+                        self.help = true;
+                    }
+                },
+                Some(Action::Save) => self.save(),
+                Some(Action::Back) => return Some(EditorAction::Exit),
+                Some(Action::Help) => self.help = !self.help,
+                Some(Action::Win) => self.cycle_win_condition(),
+                Some(Action::Gravity) => self.cycle_planet(),
+                Some(Action::RandomAsteroids) => {
+                    self.level.random_asteroids = !self.level.random_asteroids;
+                }
+                Some(Action::Pickups) => self.level.pickups = !self.level.pickups,
+                Some(Action::Weapons) => self.level.weapons = !self.level.weapons,
+                Some(Action::Craters) => self.level.craters = !self.level.craters,
+                Some(action) => self.edit_selection(action),
+                None => {}
+            }
+        }
+
+        if !left {
+            self.dragging = false;
+        }
+        let inside = mouse_x >= 0
+            && mouse_x < self.level.arena.width
+            && mouse_y >= 0
+            && mouse_y < self.level.arena.height;
+        if !inside {
+            return None;
+        }
+
+        match self.tool {
+            Tool::Pads => self.use_pads(ctx.left_click, left, mouse_x, mouse_y),
+            Tool::Terrain => self.use_terrain(left, right, mouse_x, mouse_y),
+            Tool::Spawns => {
+                if ctx.left_click {
+                    self.use_spawns(mouse_x, mouse_y);
+                }
+            }
+        }
+        None
+    }
+
+    /// The render function draws the level being edited in the colors of the given theme, the spawn markers and the
+    /// editor's help if it is shown, in the language of the given catalog and with the keys of the given bindings.
+    pub fn render(&self, ctx: &mut BTerm, theme: &Theme, catalog: &Catalog, bindings: &Bindings) {
+        let key = |action| catalog.key(bindings.key(action));
+        let state = |enabled| catalog.text(if enabled { "options.on" } else { "options.off" });
+
+        ctx.set_active_console(1);
+        ctx.cls();
+        ctx.set_active_console(0);
+        ctx.cls();
+
+        let height = self.level.arena.height;
+        let width = self.level.arena.width;
+
+        // The border of the arena, when it is smaller than the window
+        for x in 0..width {
+            ctx.set(x, height, DARK_GRAY, BLACK, to_cp437('-'));
+        }
+        for y in 0..height {
+            ctx.set(width, y, DARK_GRAY, BLACK, to_cp437('|'));
+        }
+
+        Terrain {
+            heights: self.level.terrain.clone(),
+        }
+        .render(ctx, height, theme);
+
+        self.level.wells.iter().for_each(|well| well.render(ctx, theme));
+
+        for (index, pad) in self.level.pads.iter().enumerate() {
+            let mut landing_pad = Editor::landing_pad(pad, height);
+            landing_pad.render(ctx, theme);
+            if self.tool == Tool::Pads && self.selected == Some(index) {
+                ctx.print_color(
+                    pad.x - pad.width / 2,
+                    height - pad.heigth - 1,
+                    theme.highlight,
+                    BLACK,
+                    catalog.format(
+                        "editor.pad",
+                        &[
+                            ("width", &pad.width),
+                            ("height", &pad.heigth),
+                            ("key", &key(Action::Motion)),
+                            ("motion", &format!("{:?}", pad.motion)),
+                        ],
+                    ),
+                );
+            }
+        }
+
+        for (index, spawn) in self.level.spawns.iter().enumerate() {
+            let selected = self.tool == Tool::Spawns && self.selected == Some(index);
+            let color = if selected { theme.highlight } else { theme.asteroid };
+            let heading = spawn.heading.to_radians();
+            for step in 1..4 {
+                let distance = step as f32 * (1.0 + spawn.speed * 2.0);
+                ctx.set(
+                    (spawn.x + heading.cos() * distance) as i32,
+                    (spawn.y + heading.sin() * distance) as i32,
+                    color,
+                    BLACK,
+                    to_cp437('.'),
+                );
+            }
+            ctx.set(spawn.x as i32, spawn.y as i32, color, BLACK, to_cp437('@'));
+            if selected {
+                ctx.print_color(
+                    spawn.x as i32 + 2,
+                    spawn.y as i32,
+                    theme.highlight,
+                    BLACK,
+                    catalog.format(
+                        "editor.spawn",
+                        &[
+                            ("tick", &spawn.tick),
+                            ("heading", &format!("{:.0}", spawn.heading)),
+                            ("speed", &format!("{:.2}", spawn.speed)),
+                            ("size", &format!("{:.1}", spawn.size)),
+                        ],
+                    ),
+                );
+            }
+        }
+
+        if !self.help {
+            return;
+        }
+        let tool = match self.tool {
+            Tool::Pads => catalog.format(
+                "editor.pads",
+                &[
+                    ("key", &key(Action::PadsTool)),
+                    ("smaller", &key(Action::Smaller)),
+                    ("larger", &key(Action::Larger)),
+                    ("motion", &key(Action::Motion)),
+                    ("remove", &key(Action::Remove)),
+                ],
+            ),
+            Tool::Terrain => catalog.hint("editor.terrain", bindings, Action::TerrainTool),
+            Tool::Spawns => catalog.format(
+                "editor.spawns",
+                &[
+                    ("key", &key(Action::SpawnsTool)),
+                    ("left", &key(Action::TurnLeft)),
+                    ("right", &key(Action::TurnRight)),
+                    ("faster", &key(Action::Faster)),
+                    ("slower", &key(Action::Slower)),
+                    ("later", &key(Action::Later)),
+                    ("earlier", &key(Action::Earlier)),
+                    ("smaller", &key(Action::Smaller)),
+                    ("larger", &key(Action::Larger)),
+                ],
+            ),
+        };
+        ctx.print_color(0, 0, theme.title, BLACK, tool);
+        ctx.print(
+            0,
+            1,
+            catalog.format(
+                "editor.settings",
+                &[
+                    ("win_key", &key(Action::Win)),
+                    ("win", &format!("{:?}", self.level.win)),
+                    ("random_key", &key(Action::RandomAsteroids)),
+                    ("random", &state(self.level.random_asteroids)),
+                    ("pickups_key", &key(Action::Pickups)),
+                    ("pickups", &state(self.level.pickups)),
+                    ("weapons_key", &key(Action::Weapons)),
+                    ("weapons", &state(self.level.weapons)),
+                    ("craters_key", &key(Action::Craters)),
+                    ("craters", &state(self.level.craters)),
+                ],
+            ),
+        );
+        let planet = match self.level.planet {
+            Some(planet) => format!("{:?}", planet),
+            None => catalog.text("editor.custom"),
+        };
+        ctx.print(
+            0,
+            2,
+            catalog.format(
+                "editor.commands",
+                &[
+                    ("gravity_key", &key(Action::Gravity)),
+                    ("gravity", &planet),
+                    ("test", &key(Action::TestPlay)),
+                    ("save", &key(Action::Save)),
+                    ("back", &key(Action::Back)),
+                    ("help", &key(Action::Help)),
+                ],
+            ),
+        );
+        let status = match &self.status {
+            Status::Editing => catalog.format("editor.editing", &[("path", &self.path.display())]),
+            Status::Saved => catalog.format("editor.saved", &[("path", &self.path.display())]),
+            Status::SaveFailed(error) => catalog.format("editor.save_failed", &[("error", error)]),
+            Status::TestFailed(error) => catalog.format("editor.test_failed", &[("error", error)]),
+        };
+        ctx.print_color(0, 3, theme.positive, BLACK, status);
+    }
+
+    fn select_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.selected = None;
+        self.dragging = false;
+    }
+
+    /// The use_pads function places a pad where the player clicked, or selects the pad under the mouse. While the
+    /// button is held the selected pad follows the mouse, its surface at the mouse's height.
+    fn use_pads(&mut self, click: bool, held: bool, mouse_x: i32, mouse_y: i32) {
+        let height = self.level.arena.height;
+        if click {
+            let hit = self.level.pads.iter().position(|pad| {
+                (mouse_x - pad.x).abs() <= pad.width / 2
+                    && mouse_y >= height - pad.heigth
+                    && mouse_y <= height
+            });
+            self.selected = match hit {
+                Some(index) => Some(index),
+                None => {
+                    self.level.pads.push(PadDefinition {
+                        x: mouse_x,
+                        heigth: (height - mouse_y).max(1),
+                        width: 5,
+                        motion: PadMotion::Static,
+                    });
+                    Some(self.level.pads.len() - 1)
+                }
+            };
+            self.dragging = true;
+        }
+
+        if held && self.dragging {
+            if let Some(pad) = self.selected.and_then(|index| self.level.pads.get_mut(index)) {
+                pad.x = mouse_x;
+                pad.heigth = (height - mouse_y).clamp(1, height - 1);
+            }
+        }
+    }
+
+    /// The use_terrain function paints the ground's height in the column under the mouse.
+    fn use_terrain(&mut self, raise: bool, flatten: bool, mouse_x: i32, mouse_y: i32) {
+        if !raise && !flatten {
+            return;
+        }
+        let (width, height) = (self.level.arena.width, self.level.arena.height);
+        if self.level.terrain.len() != width as usize {
+            self.level.terrain.resize(width as usize, 0);
+        }
+        // The ground stays under the top row, the way the pads do
+        self.level.terrain[mouse_x as usize] = if raise { (height - mouse_y).min(height - 1) } else { 0 };
+        if self.level.terrain.iter().all(|column| *column == 0) {
+            self.level.terrain.clear();
+        }
+    }
+
+    /// The use_spawns function selects the spawn marker under the mouse, or drops a new one with the current settings.
+    fn use_spawns(&mut self, mouse_x: i32, mouse_y: i32) {
+        let hit = self.level.spawns.iter().position(|spawn| {
+            spawn.x as i32 == mouse_x && spawn.y as i32 == mouse_y
+        });
+        self.selected = match hit {
+            Some(index) => Some(index),
+            None => {
+                self.level.spawns.push(SpawnDefinition {
+                    x: mouse_x as f32,
+                    y: mouse_y as f32,
+                    ..self.spawn
+                });
+                Some(self.level.spawns.len() - 1)
+            }
+        };
+    }
+
+    /// The edit_selection function applies the given action to the selected pad or spawn marker. Without a selected
+    /// marker, the spawn actions change the settings of the next marker.
+    fn edit_selection(&mut self, action: Action) {
+        match self.tool {
+            Tool::Pads => {
+                let Some(index) = self.selected else {
+                    return;
+                };
+                match action {
+                    Action::Smaller => {
+                        self.level.pads[index].width = (self.level.pads[index].width - 2).max(1)
+                    }
+                    Action::Larger => self.level.pads[index].width += 2,
+                    Action::Motion => self.cycle_motion(index),
+                    Action::Remove => {
+                        self.level.pads.remove(index);
+                        self.selected = None;
+                    }
+                    _ => {}
+                }
+            }
+            Tool::Spawns => {
+                if action == Action::Remove {
+                    if let Some(index) = self.selected.take() {
+                        self.level.spawns.remove(index);
+                    }
+                    return;
+                }
+                let spawn = match self.selected {
+                    Some(index) => &mut self.level.spawns[index],
+                    None => &mut self.spawn,
+                };
+                match action {
+                    Action::TurnLeft => spawn.heading = (spawn.heading - 5.0).rem_euclid(360.0),
+                    Action::TurnRight => spawn.heading = (spawn.heading + 5.0).rem_euclid(360.0),
+                    Action::Faster => spawn.speed = (spawn.speed + 0.05).min(2.0),
+                    Action::Slower => spawn.speed = (spawn.speed - 0.05).max(0.05),
+                    Action::Later => spawn.tick += 30,
+                    Action::Earlier => spawn.tick = spawn.tick.saturating_sub(30),
+                    Action::Smaller => spawn.size = (spawn.size - 0.5).max(1.0),
+                    Action::Larger => spawn.size = (spawn.size + 0.5).min(8.0),
+                    _ => {}
+                }
+            }
+            Tool::Terrain => {}
+        }
+    }
+
+    fn cycle_win_condition(&mut self) {
+        self.level.win = match self.level.win {
+            WinCondition::Endless => WinCondition::Survive { ticks: 3000 },
+            WinCondition::Survive { .. } => WinCondition::Score { points: 10 },
+            WinCondition::Score { .. } => WinCondition::LandOnEveryPad,
+            WinCondition::LandOnEveryPad => WinCondition::PadSequence,
+            WinCondition::PadSequence => WinCondition::Endless,
+        };
+    }
+
+    /// The cycle_motion function gives the next motion to the pad at the given index, with distances which fit the
+    /// arena's size. A pad with a motion that doesn't fit its place gets a smaller one.
+    fn cycle_motion(&mut self, index: usize) {
+        let pad = &mut self.level.pads[index];
+        let ArenaSize { width, height } = self.level.arena;
+        pad.motion = match pad.motion {
+            PadMotion::Static => PadMotion::Patrol {
+                distance: 8
+                    .min(pad.x - pad.width / 2)
+                    .min(width - 1 - pad.x - pad.width / 2)
+                    .max(0),
+                period: 160,
+            },
+            PadMotion::Patrol { .. } => PadMotion::Elevator {
+                distance: 6.min(pad.heigth - 1).min(height - 1 - pad.heigth),
+                period: 240,
+            },
+            PadMotion::Elevator { .. } => PadMotion::Shrinking {
+                min_width: 1,
+                period: 200,
+            },
+            PadMotion::Shrinking { .. } => PadMotion::Static,
+        };
+    }
+
+    fn cycle_planet(&mut self) {
+        self.level.planet = match self.level.planet {
+            None => Some(Planet::Moon),
+            Some(Planet::Moon) => Some(Planet::Mars),
+            Some(Planet::Mars) => Some(Planet::Titan),
+            Some(Planet::Titan) => None,
+        };
+    }
+
+    /// The save function checks the level and writes it to the editor's path.
+    fn save(&mut self) {
+        self.status = match self
+            .level
+            .validate()
+            .and_then(|_| self.level.save(&self.path))
+        {
+            Ok(()) => Status::Saved,
+            Err(error) => Status::SaveFailed(error.to_string()),
+        };
+        self.help = true;
+    }
+
+    fn landing_pad(pad: &PadDefinition, height: i32) -> LandingPad {
+        LandingPad::placed(pad.x, height, pad.heigth, pad.width, pad.motion)
+    }
+}
@@ -0,0 +1,382 @@
+use crate::character::ShipInput;
+use crate::level::Level;
+use crate::world::World;
+use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The RewardConfig struct sets how much each outcome of a step is worth to the agent. Setting a reward to 0.0
+/// disables it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RewardConfig {
+    /// The survival reward is given for every step the ship survives.
+    pub survival: f32,
+    /// The landing reward is given every time the ship touches down on the landing pad.
+    pub landing: f32,
+    /// The score reward is given for every point scored.
+    pub score: f32,
+    /// The crash reward is given once when the ship is destroyed, it is usually negative.
+    pub crash: f32,
+    /// The win reward is given once when the level's win condition is met, which ends the episode.
+    #[serde(default = "RewardConfig::default_win")]
+    pub win: f32,
+}
+
+impl RewardConfig {
+    fn default_win() -> f32 {
+        10.0
+    }
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        RewardConfig {
+            survival: 0.01,
+            landing: 1.0,
+            score: 1.0,
+            crash: -10.0,
+            win: RewardConfig::default_win(),
+        }
+    }
+}
+
+/// The EnvConfig struct holds the settings of a RoverEnv.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnvConfig {
+    /// The nearest field is the number of asteroids described in each observation.
+    pub nearest: usize,
+    /// The max_steps field ends an episode after that many steps, even if the ship is still flying.
+    pub max_steps: u64,
+    /// The rewards field sets the value of each outcome.
+    pub rewards: RewardConfig,
+    /// The level field is the level played in every episode, the endless game by default.
+    pub level: Level,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            nearest: 5,
+            max_steps: 10_000,
+            rewards: RewardConfig::default(),
+            level: Level::default(),
+        }
+    }
+}
+
+/// The ShipObservation struct describes the agent's ship.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShipObservation {
+    pub x: f32,
+    pub y: f32,
+    pub vertical: f32,
+    pub horizontal: f32,
+    pub landed: bool,
+}
+
+/// The AsteroidObservation struct describes an asteroid relative to the agent's ship.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AsteroidObservation {
+    /// The horizontal distance from the ship to the asteroid.
+    pub dx: f32,
+    /// The vertical distance from the ship to the asteroid.
+    pub dy: f32,
+    /// The horizontal speed of the asteroid.
+    pub vx: f32,
+    /// The vertical speed of the asteroid.
+    pub vy: f32,
+    /// The radius under which the asteroid collides with the ship.
+    pub radius: f32,
+}
+
+/// The PadObservation struct describes the nearest landing pad relative to the agent's ship.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PadObservation {
+    /// The horizontal distance from the ship to the middle of the pad.
+    pub dx: f32,
+    /// The vertical distance from the ship to the top of the pad.
+    pub dy: f32,
+    /// The half width of the pad's landing surface.
+    pub half_width: f32,
+}
+
+/// The Observation struct is what the agent sees of the world after each step.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Observation {
+    pub ship: ShipObservation,
+    /// The asteroids field lists the nearest asteroids, closest first. It can hold less than EnvConfig::nearest
+    /// asteroids at the start of a game.
+    pub asteroids: Vec<AsteroidObservation>,
+    pub pad: PadObservation,
+}
+
+impl Observation {
+    /// The SHIP_FEATURES constant is the number of values describing the ship in a flattened observation.
+    pub const SHIP_FEATURES: usize = 5;
+    /// The ASTEROID_FEATURES constant is the number of values describing each asteroid in a flattened observation.
+    pub const ASTEROID_FEATURES: usize = 5;
+    /// The PAD_FEATURES constant is the number of values describing the pad in a flattened observation.
+    pub const PAD_FEATURES: usize = 3;
+
+    /// The to_vec function flattens the observation into a fixed-size vector, as most learning libraries expect.
+    /// The ship comes first, then the pad, then the nearest asteroids; missing asteroids are padded with zeros.
+    pub fn to_vec(&self, nearest: usize) -> Vec<f32> {
+        let mut values = Vec::with_capacity(
+            Self::SHIP_FEATURES + Self::PAD_FEATURES + nearest * Self::ASTEROID_FEATURES,
+        );
+        values.extend([
+            self.ship.x,
+            self.ship.y,
+            self.ship.vertical,
+            self.ship.horizontal,
+            if self.ship.landed { 1.0 } else { 0.0 },
+        ]);
+        values.extend([self.pad.dx, self.pad.dy, self.pad.half_width]);
+        for index in 0..nearest {
+            match self.asteroids.get(index) {
+                Some(asteroid) => values.extend([
+                    asteroid.dx,
+                    asteroid.dy,
+                    asteroid.vx,
+                    asteroid.vy,
+                    asteroid.radius,
+                ]),
+                None => values.extend([0.0; Self::ASTEROID_FEATURES]),
+            }
+        }
+        values
+    }
+}
+
+/// The Info struct gives details about a step that are not part of the observation.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Info {
+    pub score: usize,
+    pub tick: u64,
+    pub landings: usize,
+    /// The truncated field is set when the episode ended because it reached EnvConfig::max_steps.
+    pub truncated: bool,
+    /// The won field is set when the episode ended because the level's win condition was met.
+    pub won: bool,
+}
+
+/// The RoverEnv struct wraps a single-ship World behind the reset/step interface used by reinforcement learning
+/// libraries. The game rules are the ones of the real game, as the same World is stepped.
+pub struct RoverEnv {
+    pub config: EnvConfig,
+    world: World,
+    landings: usize,
+    /// The done field is set once the episode is over, until the next reset.
+    done: bool,
+}
+
+impl RoverEnv {
+    /// The new function creates an environment with the given configuration, reset must be called before stepping.
+    pub fn new(config: EnvConfig) -> Self {
+        RoverEnv {
+            config,
+            world: World::seeded(1, 0),
+            landings: 0,
+            done: false,
+        }
+    }
+
+    /// The reset function starts a new episode, the same seed always produces the same asteroids and landing pad.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.world = World::from_level(
+            &self.config.level,
+            1,
+            RandomNumberGenerator::seeded(seed),
+        );
+        self.landings = 0;
+        self.done = false;
+        self.observe()
+    }
+
+    /// The step function flies the ship with the given action for one frame and returns the new observation, the
+    /// reward earned during the step, whether the episode is over, and some extra information. Once the episode is
+    /// over, the world is no longer stepped and no reward is given until the next reset.
+    pub fn step(&mut self, action: ShipInput) -> (Observation, f32, bool, Info) {
+        if self.done {
+            return (self.observe(), 0.0, true, self.info());
+        }
+
+        let rewards = &self.config.rewards;
+        let score = self.world.score;
+        let was_landed = self.world.characters[0].landed;
+
+        self.world.step(&[action]);
+
+        let character = &self.world.characters[0];
+        let mut reward = 0.0;
+        if character.destroyed {
+            reward += rewards.crash;
+        } else {
+            reward += rewards.survival;
+        }
+        if character.landed && !was_landed {
+            reward += rewards.landing;
+            self.landings += 1;
+        }
+        reward += (self.world.score - score) as f32 * rewards.score;
+        if self.world.is_won() {
+            reward += rewards.win;
+        }
+
+        let info = self.info();
+        self.done = character.destroyed || info.won || info.truncated;
+        (self.observe(), reward, self.done, info)
+    }
+
+    /// The info function gives the details of the current state of the episode.
+    fn info(&self) -> Info {
+        let won = self.world.is_won();
+        Info {
+            score: self.world.score,
+            tick: self.world.tick,
+            landings: self.landings,
+            // A won episode ends because of the win, even on its last allowed step
+            truncated: !won && self.world.tick >= self.config.max_steps,
+            won,
+        }
+    }
+
+    /// The action_count function returns the number of discrete actions accepted by action_from_index, the fire bit
+    /// is only counted when the level has weapons.
+    pub fn action_count(&self) -> u8 {
+        if self.config.level.weapons {
+            16
+        } else {
+            8
+        }
+    }
+
+    /// The action function returns the thrusters fired by the discrete action of the given index, see
+    /// action_from_index. An index of action_count or more is an error, rather than an action whose extra bits are
+    /// silently ignored.
+    pub fn action(&self, index: u8) -> Result<ShipInput, String> {
+        let count = self.action_count();
+        if index >= count {
+            return Err(format!(
+                "Invalid action {}: the actions go from 0 to {}",
+                index,
+                count - 1
+            ));
+        }
+        Ok(action_from_index(index))
+    }
+
+    /// The world function gives read access to the simulated world, for example to render it.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The observe function builds the observation of the current world.
+    fn observe(&self) -> Observation {
+        let character = &self.world.characters[0];
+        let ship = character.coordinate;
+        let arena = &self.world.arena;
+        let landing_pad = arena
+            .landing_pads
+            .iter()
+            .min_by(|a, b| {
+                (a.x as f32 - ship.x)
+                    .abs()
+                    .total_cmp(&(b.x as f32 - ship.x).abs())
+            })
+            .expect("a world always has a landing pad");
+
+        let mut asteroids: Vec<AsteroidObservation> = self
+            .world
+            .asteroids
+            .iter()
+            .map(|asteroid| AsteroidObservation {
+                dx: asteroid.coordinate.x - ship.x,
+                dy: asteroid.coordinate.y - ship.y,
+                vx: asteroid.trajectory.cos() * asteroid.speed,
+                vy: asteroid.trajectory.sin() * asteroid.speed,
+                radius: asteroid.scaling.x / 2.0,
+            })
+            .collect();
+        asteroids.sort_by(|a, b| {
+            (a.dx * a.dx + a.dy * a.dy).total_cmp(&(b.dx * b.dx + b.dy * b.dy))
+        });
+        asteroids.truncate(self.config.nearest);
+
+        Observation {
+            ship: ShipObservation {
+                x: ship.x,
+                y: ship.y,
+                vertical: character.momentum.vertical,
+                horizontal: character.momentum.horizontal,
+                landed: character.landed,
+            },
+            asteroids,
+            pad: PadObservation {
+                dx: landing_pad.x as f32 - ship.x,
+                dy: (arena.height - landing_pad.heigth) as f32 - ship.y,
+                half_width: (landing_pad.pad_width / 2) as f32,
+            },
+        }
+    }
+}
+
+/// The action_from_index function turns a discrete action, as used by most agents, into the thrusters to fire.
+/// The index is read as a bit field: 1 thrusts up, 2 thrusts left, 4 thrusts right and 8 fires, so there are 8
+/// actions, or 16 in levels with weapons.
+pub fn action_from_index(index: u8) -> ShipInput {
+    ShipInput {
+        up: index & 1 != 0,
+        left: index & 2 != 0,
+        right: index & 4 != 0,
+        fire: index & 8 != 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_from_index_reads_a_bit_field() {
+        assert_eq!(action_from_index(0), ShipInput::default());
+        let all = action_from_index(15);
+        assert!(all.up && all.left && all.right && all.fire);
+        let right = action_from_index(4);
+        assert!(right.right && !right.up && !right.left && !right.fire);
+        let inputs: Vec<ShipInput> = (0..16).map(action_from_index).collect();
+        for (index, input) in inputs.iter().enumerate() {
+            assert!(!inputs[..index].contains(input));
+        }
+    }
+
+    #[test]
+    fn action_refuses_indexes_past_the_count() {
+        let mut env = RoverEnv::new(EnvConfig::default());
+        assert_eq!(env.action_count(), 8);
+        assert_eq!(env.action(7), Ok(action_from_index(7)));
+        assert!(env.action(8).is_err());
+
+        env.config.level.weapons = true;
+        assert_eq!(env.action_count(), 16);
+        assert_eq!(env.action(15), Ok(action_from_index(15)));
+        assert_eq!(env.action(16).unwrap_err(), "Invalid action 16: the actions go from 0 to 15");
+    }
+
+    #[test]
+    fn episodes_are_reproducible() {
+        let run = |seed| {
+            let mut env = RoverEnv::new(EnvConfig::default());
+            let first = env.reset(seed).to_vec(5);
+            let mut rewards = Vec::new();
+            for tick in 0..300 {
+                let (_, reward, done, _) = env.step(action_from_index((tick % 8) as u8));
+                rewards.push(reward);
+                if done {
+                    break;
+                }
+            }
+            (first, rewards, env.world().score)
+        };
+        assert_eq!(run(3), run(3));
+    }
+}
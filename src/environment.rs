@@ -0,0 +1,292 @@
+use crate::i18n::Catalog;
+use crate::theme::Theme;
+use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use std::ops::AddAssign;
+
+/// The Vector struct is a force with a horizontal and a vertical component, positive towards the right and the bottom
+/// of the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vector {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vector {
+    /// The length function returns the magnitude of the vector.
+    pub fn length(self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    /// The scale function returns the vector multiplied by the given factor.
+    pub fn scale(self, factor: f32) -> Vector {
+        Vector {
+            x: self.x * factor,
+            y: self.y * factor,
+        }
+    }
+}
+
+impl AddAssign for Vector {
+    fn add_assign(&mut self, other: Vector) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+/// The Wind struct describes the wind blowing across the arena. It blows at a constant speed, on top of which gusts
+/// come and go over the period; positive speeds blow towards the right.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Wind {
+    pub speed: f32,
+    /// The gust field is the largest change of speed brought by the gusts.
+    pub gust: f32,
+    /// The period field is the number of ticks between two of the strongest gusts.
+    pub period: u64,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Wind {
+            speed: 0.0,
+            gust: 0.0,
+            period: 240,
+        }
+    }
+}
+
+impl Wind {
+    /// The at function returns the speed of the wind at the given tick. The gusts are the sum of two waves, so that
+    /// they don't feel too regular, but they are the same for everyone playing the same level.
+    pub fn at(&self, tick: u64) -> f32 {
+        if self.gust == 0.0 || self.period == 0 {
+            return self.speed;
+        }
+        let phase = (tick % self.period) as f32 / self.period as f32 * TAU;
+        self.speed + self.gust * (0.7 * phase.sin() + 0.3 * (phase * 3.0).sin())
+    }
+}
+
+/// The Environment struct describes the forces applied to the ships: the gravity, the drag of the atmosphere, which
+/// slows the ships down relative to the air, and the wind, which carries the air along. See
+/// Character::apply_environment.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Environment {
+    pub gravity: Vector,
+    pub drag: f32,
+    pub wind: Wind,
+}
+
+impl Default for Environment {
+    /// The default environment is the one of the endless game, without wind.
+    fn default() -> Self {
+        Environment {
+            gravity: Vector { x: 0.0, y: 0.4 },
+            drag: 0.2,
+            wind: Wind::default(),
+        }
+    }
+}
+
+/// The Planet enum lists the ready-made environments a level can be set on.
+///
+/// # Variants
+/// - Moon: Low gravity and no atmosphere, the ship drifts for a long time once pushed
+/// - Mars: Light gravity and a thin atmosphere, with strong gusting crosswinds
+/// - Titan: Very low gravity in a thick atmosphere, the ship floats down slowly and a steady breeze blows
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Planet {
+    Moon,
+    Mars,
+    Titan,
+}
+
+impl Planet {
+    /// The environment function returns the forces of the planet.
+    pub fn environment(self) -> Environment {
+        match self {
+            Planet::Moon => Environment {
+                gravity: Vector { x: 0.0, y: 0.15 },
+                drag: 0.02,
+                wind: Wind::default(),
+            },
+            Planet::Mars => Environment {
+                gravity: Vector { x: 0.0, y: 0.25 },
+                drag: 0.06,
+                wind: Wind {
+                    speed: 0.2,
+                    gust: 0.25,
+                    period: 300,
+                },
+            },
+            Planet::Titan => Environment {
+                gravity: Vector { x: 0.0, y: 0.12 },
+                drag: 0.35,
+                wind: Wind {
+                    speed: -0.1,
+                    gust: 0.05,
+                    period: 600,
+                },
+            },
+        }
+    }
+}
+
+/// The WellKind enum lists the kinds of gravity wells.
+///
+/// # Variants
+/// - Planetoid: A large rocky body with a moderate pull, asteroids can be put in orbit around it
+/// - BlackHole: A tiny body with a huge pull, whatever crosses its horizon is swallowed
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WellKind {
+    Planetoid,
+    BlackHole,
+}
+
+/// The GravityWell struct is a point mass pulling the ships, the asteroids and the pickups towards its center with a
+/// force inversely proportional to the square of their distance, which lets asteroids swing around it or orbit it.
+/// The radius is its surface, or its horizon for a black hole: whatever gets inside is destroyed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GravityWell {
+    pub x: f32,
+    pub y: f32,
+    /// The mass field is the pull of the well at a distance of 1, the pull at a distance d is mass / d².
+    pub mass: f32,
+    pub radius: f32,
+    pub kind: WellKind,
+}
+
+impl GravityWell {
+    /// The force_at function returns the pull of the well on a body at the given position. The pull stops growing
+    /// below the well's radius, so that a body grazing it is not flung away at an absurd speed.
+    pub fn force_at(&self, x: f32, y: f32) -> Vector {
+        let offset = Vector {
+            x: self.x - x,
+            y: self.y - y,
+        };
+        let distance = offset.length();
+        if distance == 0.0 {
+            return Vector::default();
+        }
+        let clamped = distance.max(self.radius);
+        offset.scale(self.mass / (clamped * clamped) / distance)
+    }
+
+    /// The orbital_speed function returns the speed a body needs to orbit the well on a circle of the given radius.
+    pub fn orbital_speed(&self, distance: f32) -> f32 {
+        (self.mass / distance).sqrt()
+    }
+
+    /// The contains function returns true if the given position is inside the well's radius.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let offset = Vector {
+            x: self.x - x,
+            y: self.y - y,
+        };
+        offset.length() < self.radius
+    }
+
+    /// The render function is used to draw the well on the fancy-console (id 1), behind everything else, then shift
+    /// back to the main console (id 0). A black hole is drawn as a dark disc inside a glowing ring.
+    pub fn render(&self, ctx: &mut BTerm, theme: &Theme) {
+        let center = PointF::new(self.x, self.y);
+        let size = self.radius * 2.0;
+        ctx.set_active_console(1);
+        match self.kind {
+            WellKind::Planetoid => ctx.set_fancy(
+                center,
+                0,
+                Degrees::new(0.0),
+                PointF::new(size, size),
+                theme.planetoid,
+                BLACK,
+                to_cp437('O'),
+            ),
+            WellKind::BlackHole => {
+                ctx.set_fancy(
+                    center,
+                    0,
+                    Degrees::new(0.0),
+                    PointF::new(size * 2.0, size * 2.0),
+                    theme.black_hole,
+                    BLACK,
+                    to_cp437('o'),
+                );
+                ctx.set_fancy(
+                    center,
+                    0,
+                    Degrees::new(0.0),
+                    PointF::new(size, size),
+                    DARK_GRAY,
+                    BLACK,
+                    to_cp437('*'),
+                );
+            }
+        }
+        ctx.set_active_console(0);
+    }
+}
+
+/// The render_wind function draws the wind indicator in the top right corner of the screen: arrows pointing where the
+/// wind blows, one per 0.1 of speed, and the speed itself, labelled in the language of the given catalog.
+pub fn render_wind(ctx: &mut BTerm, wind: f32, screen_width: i32, theme: &Theme, catalog: &Catalog) {
+    if wind.abs() < 0.01 {
+        return;
+    }
+    let arrows = ((wind.abs() * 10.0).round() as usize).clamp(1, 5);
+    let arrow = if wind > 0.0 { ">" } else { "<" };
+    let text = catalog.format(
+        "play.wind",
+        &[
+            ("arrows", &format!("{:<5}", arrow.repeat(arrows))),
+            ("speed", &format!("{:+.2}", wind)),
+        ],
+    );
+    ctx.print_color(
+        screen_width - text.chars().count() as i32,
+        1,
+        theme.wind,
+        BLACK,
+        text,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind_without_gusts_is_steady() {
+        let steady = Wind {
+            speed: 0.3,
+            ..Wind::default()
+        };
+        assert!((0..500).all(|tick| steady.at(tick) == 0.3));
+
+        let no_period = Wind {
+            speed: 0.3,
+            gust: 1.0,
+            period: 0,
+        };
+        assert_eq!(no_period.at(17), 0.3);
+    }
+
+    #[test]
+    fn wind_gusts_repeat_over_the_period() {
+        let wind = Wind {
+            speed: -0.2,
+            gust: 0.5,
+            period: 120,
+        };
+        assert!((wind.at(0) - wind.speed).abs() < 1e-6);
+        for tick in 0..240 {
+            assert_eq!(wind.at(tick), wind.at(tick + wind.period));
+            assert!((wind.at(tick) - wind.speed).abs() <= wind.gust + 1e-6);
+        }
+        assert!((0..120).any(|tick| wind.at(tick) > wind.speed + 0.3));
+        assert!((0..120).any(|tick| wind.at(tick) < wind.speed - 0.3));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::character::Character;
+use crate::storage;
+use crate::theme::Theme;
+use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The version of the ghost files written by this version of the game.
+const GHOST_VERSION: u32 = 1;
+
+/// The Ghost struct is the recorded flight of a ship: its position after every tick of a run. It is raced against
+/// by drawing it next to the live ship, as a translucent ship following the same course as on the recorded run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ghost {
+    pub version: u32,
+    /// The label field tells what was recorded, such as "Time attack #1234", it is shown while racing the ghost.
+    pub label: String,
+    /// The score field is the score of the recorded run, it tells which of two daily runs was the best.
+    pub score: usize,
+    /// The points field holds the position of the ship after each tick, the first one after the first tick.
+    pub points: Vec<(f32, f32)>,
+}
+
+impl Default for Ghost {
+    fn default() -> Self {
+        Ghost {
+            version: GHOST_VERSION,
+            label: String::new(),
+            score: 0,
+            points: Vec::new(),
+        }
+    }
+}
+
+impl Ghost {
+    /// The time_attack_name function returns the name of the save file of the personal best of a time attack course.
+    pub fn time_attack_name(seed: u64) -> String {
+        format!("ghosts/time_attack_{}.json", seed)
+    }
+
+    /// The daily_name function returns the name of the save file of the best run of a day's challenge.
+    pub fn daily_name(date: &str) -> String {
+        format!("ghosts/daily_{}.json", date)
+    }
+
+    /// The load function reads a personal best from the data directory, it returns None if there is none yet.
+    pub fn load(name: &str) -> Option<Ghost> {
+        let ghost: Ghost = storage::load(name);
+        if ghost.points.is_empty() || ghost.version > GHOST_VERSION {
+            return None;
+        }
+        Some(ghost)
+    }
+
+    /// The import function reads a ghost file shared by another player.
+    pub fn import(path: &Path) -> Result<Ghost, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let ghost: Ghost =
+            serde_json::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error))?;
+        if ghost.version > GHOST_VERSION {
+            return Err(format!(
+                "{}: ghost version {} is not supported",
+                path.display(),
+                ghost.version
+            ));
+        }
+        Ok(ghost)
+    }
+
+    /// The save function writes the ghost as a personal best in the data directory.
+    pub fn save(&self, name: &str) {
+        if let Err(error) = storage::save(name, self) {
+            eprintln!("Could not save the ghost: {}", error);
+        }
+    }
+
+    /// The record function adds the position of the character after a tick.
+    pub fn record(&mut self, character: &Character) {
+        self.points
+            .push((character.coordinate.x, character.coordinate.y));
+    }
+
+    /// The render function draws the ghost where the recorded ship was after the given number of ticks, a ghost whose
+    /// run is over stays where it ended. It is drawn on the fancy-console (id 1), behind the live ship and tinted so
+    /// that it can't be mistaken for it.
+    pub fn render(&self, ctx: &mut BTerm, tick: u64, theme: &Theme) {
+        let index = (tick.saturating_sub(1) as usize).min(self.points.len().saturating_sub(1));
+        let Some((x, y)) = self.points.get(index) else {
+            return;
+        };
+        ctx.set_active_console(1);
+        ctx.set_fancy(
+            PointF::new(*x, *y),
+            0,
+            Degrees::new(0.0),
+            PointF::new(1.0, 1.0),
+            RGBA::from_u8(theme.ghost.0, theme.ghost.1, theme.ghost.2, 110),
+            RGBA::from_u8(0, 0, 0, 0),
+            to_cp437('^'),
+        );
+        ctx.set_active_console(0);
+    }
+}
@@ -1,27 +1,47 @@
 use crate::character::Character;
+use crate::environment::Vector;
+use crate::theme::Theme;
 use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The size of a pickup, it is smaller than any asteroid but large enough to be caught.
+const PICKUP_SIZE: f32 = 2.0;
 
 /// The Asteroid struct is used to create and manage the asteroids that will be flying around the screen.
+#[derive(Clone)]
 pub struct Asteroid {
     /// The coordinate field is used to store the position of the asteroid in floating point coordinates to allow for fancy rendering.
-    coordinate: PointF,
+    pub coordinate: PointF,
     /// The trajectory field is used to store the angle at which the asteroid is traveling.
-    trajectory: f32,
+    pub trajectory: f32,
     /// The orientation field is used to store the rotation of the asteroid, to allow for a spinning flight pattern.
-    orientation: Degrees,
+    pub orientation: Degrees,
     /// The scaling field is used to store the size of the asteroid.
-    scaling: PointF,
+    pub scaling: PointF,
     /// The speed field is used to store the speed at which the asteroid is traveling.
-    speed: f32,
+    pub speed: f32,
+    /// The scripted field is set for the asteroids spawned by a level, they fall only once instead of being sent back
+    /// to the top of the screen.
+    pub scripted: bool,
+    /// The hit_points field is the number of projectiles the asteroid can take before being destroyed, it depends
+    /// on the asteroid's size, see Asteroid::hit_points_for.
+    pub hit_points: u32,
+    /// The grazing field lists the indexes of the ships the asteroid is passing close to, and the passed field the ones
+    /// it already grazed or hit, which it can't graze anymore. See check_near_miss.
+    pub grazing: Vec<usize>,
+    pub passed: Vec<usize>,
+    /// The id field tells the asteroids apart in the world's events, it is 0 until the world gives the asteroid an id
+    /// as it enters it, see World::identify.
+    pub id: u64,
 }
 
 impl Asteroid {
     /// The new function is used to create a new instance of the Asteroid struct.
     /// It takes in an x and y coordinate and returns a new instance of the Asteroid struct.
     /// All asteroids start at the top of the screen ( y = 0 ) and have a random x coordinate.
-    /// The size, speed, and trajectory are all randomly generated.
-    pub fn new(x: f32, y: f32) -> Self {
-        let mut random = RandomNumberGenerator::new();
+    /// The size, speed, and trajectory are all randomly generated with the given random number generator, so that a
+    /// seeded generator always produces the same asteroids.
+    pub fn new(x: f32, y: f32, random: &mut RandomNumberGenerator) -> Self {
         let size: f32 = random.range(2.0, 4.0);
         Asteroid {
             coordinate: PointF::new(x, y),
@@ -29,20 +49,53 @@ impl Asteroid {
             orientation: Degrees::new(0.0),
             scaling: PointF::new(size, size),
             speed: random.range(0.2, 0.6),
+            scripted: false,
+            hit_points: Asteroid::hit_points_for(size),
+            grazing: Vec::new(),
+            passed: Vec::new(),
+            id: 0,
+        }
+    }
+
+    /// The scripted function is used to create the asteroids described by a level, with a heading in degrees
+    /// (0 travels to the right, 90 falls straight down), a speed and a size.
+    pub fn scripted(x: f32, y: f32, heading: f32, speed: f32, size: f32) -> Self {
+        Asteroid {
+            coordinate: PointF::new(x, y),
+            trajectory: heading.to_radians(),
+            orientation: Degrees::new(0.0),
+            scaling: PointF::new(size, size),
+            speed,
+            scripted: true,
+            hit_points: Asteroid::hit_points_for(size),
+            grazing: Vec::new(),
+            passed: Vec::new(),
+            id: 0,
         }
     }
 
+    /// The hit_points_for function returns the hit points of an asteroid of the given size, about one per unit of size.
+    pub fn hit_points_for(size: f32) -> u32 {
+        size.round().max(1.0) as u32
+    }
+
     /// The render function is used to draw the asteroid to the screen. Since it uses floating-point fancy-rendering, it must
     /// first shift on the fancy-console (id 1), before drawing the asteroid, and then shift back to the main console (id 0).
-    pub fn render(&mut self, ctx: &mut BTerm) {
+    /// The asteroid doesn't spin when the theme reduces motion.
+    pub fn render(&mut self, ctx: &mut BTerm, theme: &Theme) {
         ctx.set_active_console(1);
 
+        let orientation = if theme.reduced_motion {
+            Degrees::new(0.0)
+        } else {
+            self.orientation
+        };
         ctx.set_fancy(
             self.coordinate,
             1,
-            self.orientation,
+            orientation,
             self.scaling,
-            RED,
+            theme.asteroid,
             BLACK,
             to_cp437('@'),
         );
@@ -51,10 +104,21 @@ impl Asteroid {
 
     /// The update function is used to move the asteroid in the direction of its trajectory by using the cosine and sine of the trajectory
     /// to calculate the x and y components of the movement. It also updates the orientation of the asteroid to make it spin.
-    pub fn update(&mut self) {
-        self.coordinate.x += self.trajectory.cos() * self.speed;
-        self.coordinate.y += self.trajectory.sin() * self.speed;
-        self.orientation.0 += 0.2;
+    /// The time_scale parameter scales the asteroid's speed, it is 1.0 unless time is slowed down.
+    pub fn update(&mut self, time_scale: f32) {
+        self.coordinate.x += self.trajectory.cos() * self.speed * time_scale;
+        self.coordinate.y += self.trajectory.sin() * self.speed * time_scale;
+        self.orientation.0 += 0.2 * time_scale;
+    }
+
+    /// The apply_force function bends the asteroid's course with a force applied during a tick, such as the pull of a
+    /// gravity well: its trajectory and speed are turned into a velocity, the force is added to it, and the result
+    /// gives the new trajectory and speed. The time_scale parameter scales the force like the speed in update.
+    pub fn apply_force(&mut self, force: Vector, time_scale: f32) {
+        let velocity_x = self.trajectory.cos() * self.speed + force.x * time_scale;
+        let velocity_y = self.trajectory.sin() * self.speed + force.y * time_scale;
+        self.speed = (velocity_x * velocity_x + velocity_y * velocity_y).sqrt();
+        self.trajectory = velocity_y.atan2(velocity_x);
     }
 
     /// The check_finished_course function is used to check if the asteroid has left the screen. It takes in the x and y bounds of the screen
@@ -83,4 +147,101 @@ impl Asteroid {
         }
         false
     }
+
+    /// The check_near_miss function is used to check if the asteroid passed close to the character, the ship at the
+    /// given index, without hitting it. The margin is the distance added to the asteroid's collision radius. The ship
+    /// is kept track of while it is within the margin, and the function returns true once it is out of it again
+    /// without having been hit; the ship is then marked as passed, a near miss is only counted once per asteroid and
+    /// ship. An asteroid that finishes its course while a ship is within the margin also grazed it, see World::advance.
+    pub fn check_near_miss(&mut self, ship: usize, character: &Character, margin: f32) -> bool {
+        if self.passed.contains(&ship) {
+            return false;
+        }
+        let x = self.coordinate.x - character.coordinate.x;
+        let y = self.coordinate.y - character.coordinate.y;
+        let distance = (x * x + y * y).sqrt();
+        let radius = self.scaling.x / 2.0;
+        if distance < radius {
+            // The ship was hit, it can't be grazed by this asteroid anymore
+            self.grazing.retain(|grazing| *grazing != ship);
+            self.passed.push(ship);
+            return false;
+        }
+        if distance < radius + margin {
+            if !self.grazing.contains(&ship) {
+                self.grazing.push(ship);
+            }
+            return false;
+        }
+        let Some(index) = self.grazing.iter().position(|grazing| *grazing == ship) else {
+            return false;
+        };
+        self.grazing.remove(index);
+        self.passed.push(ship);
+        true
+    }
+}
+
+/// The PickupKind enum lists the power-ups a ship can collect.
+///
+/// # Variants
+/// - Shield: The ship is protected for a while, the shield absorbs the first asteroid hitting it
+/// - SlowTime: The asteroids and the pickups fall slower for a while
+/// - Shockwave: The asteroids around the ship are cleared at once
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PickupKind {
+    Shield,
+    SlowTime,
+    Shockwave,
+}
+
+/// The Pickup struct is a power-up falling from the top of the screen. It falls exactly like an asteroid, so it is
+/// built around one, but it is collected instead of destroying the ship.
+#[derive(Clone)]
+pub struct Pickup {
+    /// The body field is the falling asteroid carrying the pickup, it gives the pickup its position and its course.
+    pub body: Asteroid,
+    pub kind: PickupKind,
+}
+
+impl Pickup {
+    /// The new function is used to create a pickup of a random kind, falling from the given coordinates like a
+    /// random asteroid.
+    pub fn new(x: f32, y: f32, random: &mut RandomNumberGenerator) -> Self {
+        let mut body = Asteroid::new(x, y, random);
+        body.scaling = PointF::new(PICKUP_SIZE, PICKUP_SIZE);
+        let kind = match random.range(0, 3) {
+            0 => PickupKind::Shield,
+            1 => PickupKind::SlowTime,
+            _ => PickupKind::Shockwave,
+        };
+        Pickup { body, kind }
+    }
+
+    /// The render function is used to draw the pickup to the screen, as the initial of its effect. Like the asteroids it is
+    /// drawn on the fancy-console (id 1), but it does not spin so that it stays readable.
+    pub fn render(&self, ctx: &mut BTerm, theme: &Theme) {
+        let (color, glyph) = match self.kind {
+            PickupKind::Shield => (theme.pickup_shield, 'S'),
+            PickupKind::SlowTime => (theme.pickup_slow_time, 'T'),
+            PickupKind::Shockwave => (theme.pickup_shockwave, 'W'),
+        };
+        ctx.set_active_console(1);
+        ctx.set_fancy(
+            self.body.coordinate,
+            1,
+            Degrees::new(0.0),
+            PointF::new(1.0, 1.0),
+            color,
+            BLACK,
+            to_cp437(glyph),
+        );
+        ctx.set_active_console(0);
+    }
+
+    /// The check_collected function is used to check if the character caught the pickup, it uses the same distance
+    /// as an asteroid's collision.
+    pub fn check_collected(&mut self, character: &mut Character) -> bool {
+        self.body.check_collision(character)
+    }
 }
@@ -0,0 +1,181 @@
+use crate::bindings::{self, Action, Bindings};
+use bracket_lib::prelude::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// The Language enum lists the languages the game's texts are translated to.
+///
+/// # Variants
+/// - English: the game's original language, the one missing translations fall back to
+/// - French: the texts of locales/fr.txt
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    /// Every language, in the order they are chosen in on the options screen.
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    /// The name function returns the name of the language in that language, as shown on the options screen.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::French => "Français",
+        }
+    }
+
+    /// The next function returns the language after this one, going back to the first one after the last one.
+    pub fn next(self) -> Language {
+        let index = Language::ALL
+            .iter()
+            .position(|language| *language == self)
+            .unwrap_or(0);
+        Language::ALL[(index + 1) % Language::ALL.len()]
+    }
+
+    /// The source function returns the message catalog of the language, the catalogs are embedded in the game.
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.txt"),
+            Language::French => include_str!("../locales/fr.txt"),
+        }
+    }
+}
+
+/// The Catalog struct holds the messages of a language, each under its key. The catalogs are simple text files with a
+/// `key = value` message per line, the lines starting with `#` are comments. A message can hold placeholders such as
+/// `{score}`, replaced by the values given to the format function. For example:
+///
+/// ```text
+/// # The results screen
+/// lost.title = You lost !
+/// lost.score = Your score is : {score}
+/// ```
+pub struct Catalog {
+    pub language: Language,
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// The new function returns the catalog of the given language, the English messages are used for the keys it
+    /// doesn't translate.
+    pub fn new(language: Language) -> Self {
+        Catalog {
+            language,
+            messages: parse(language.source()),
+            fallback: parse(Language::English.source()),
+        }
+    }
+
+    /// The lookup function returns the message of the given key, or None if no catalog has it.
+    pub fn lookup(&self, key: &str) -> Option<&str> {
+        self.messages
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map(String::as_str)
+    }
+
+    /// The text function returns the message of the given key, a key missing from every catalog is returned as it is
+    /// so that it shows up on screen.
+    pub fn text(&self, key: &str) -> String {
+        self.lookup(key).unwrap_or(key).to_string()
+    }
+
+    /// The format function returns the message of the given key with its placeholders replaced by the given values.
+    pub fn format(&self, key: &str, values: &[(&str, &dyn Display)]) -> String {
+        let mut message = self.text(key);
+        for (name, value) in values {
+            message = message.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        message
+    }
+
+    /// The key function returns the name of the given key as it is shown to the player, such as "Esc" for the escape
+    /// key. The names can be translated with the `key.<name>` messages, such as `key.Space`.
+    pub fn key(&self, key: VirtualKeyCode) -> String {
+        let name = bindings::key_name(key);
+        if let Some(message) = self.lookup(&format!("key.{}", name)) {
+            return message.to_string();
+        }
+        match name.strip_prefix("Key") {
+            Some(digit) if !digit.is_empty() => digit.to_string(),
+            _ => name,
+        }
+    }
+
+    /// The hint function returns the message of the given key with its `{key}` placeholder replaced by the key bound to
+    /// the given action, so that the hints follow the rebound keys.
+    pub fn hint(&self, key: &str, bindings: &Bindings, action: Action) -> String {
+        self.format(key, &[("key", &self.key(bindings.key(action)))])
+    }
+}
+
+/// The parse function reads the messages of a catalog, a line without an equal sign is ignored.
+fn parse(source: &str) -> HashMap<String, String> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, message)| (key.trim().to_string(), message.trim().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The catalog function builds a catalog from the given messages, falling back to the given English ones.
+    fn catalog(messages: &str, fallback: &str) -> Catalog {
+        Catalog {
+            language: Language::French,
+            messages: parse(messages),
+            fallback: parse(fallback),
+        }
+    }
+
+    #[test]
+    fn parse_skips_comments_and_trims() {
+        let messages = parse("# a comment\n\n  lost.title =  Perdu !  \nnot a message\nscore = {a} = {b}");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages["lost.title"], "Perdu !");
+        assert_eq!(messages["score"], "{a} = {b}");
+    }
+
+    #[test]
+    fn format_replaces_every_placeholder() {
+        let catalog = catalog("lost.score = {score} points, {score} !", "");
+        let message = catalog.format("lost.score", &[("score", &42), ("unused", &"x")]);
+        assert_eq!(message, "42 points, 42 !");
+    }
+
+    #[test]
+    fn missing_messages_fall_back_to_english_then_to_the_key() {
+        let catalog = catalog("menu.title = Titre", "menu.title = Title\nmenu.quit = Quit ({key})");
+        assert_eq!(catalog.text("menu.title"), "Titre");
+        assert_eq!(catalog.format("menu.quit", &[("key", &"Q")]), "Quit (Q)");
+        assert_eq!(catalog.lookup("menu.missing"), None);
+        assert_eq!(catalog.format("menu.missing", &[("key", &"Q")]), "menu.missing");
+    }
+
+    #[test]
+    fn key_names_are_translated() {
+        let french = Catalog::new(Language::French);
+        assert_eq!(french.key(VirtualKeyCode::Space), "Espace");
+        assert_eq!(french.key(VirtualKeyCode::Key3), "3");
+        assert_eq!(Catalog::new(Language::English).key(VirtualKeyCode::Escape), "Esc");
+    }
+
+    #[test]
+    fn the_catalogs_translate_the_english_keys() {
+        let english = parse(Language::English.source());
+        let french = parse(Language::French.source());
+        let missing: Vec<&String> = english.keys().filter(|key| !french.contains_key(*key)).collect();
+        assert!(missing.is_empty(), "missing French messages: {:?}", missing);
+    }
+}
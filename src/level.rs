@@ -0,0 +1,588 @@
+use crate::environment::{Environment, GravityWell, Planet, WellKind};
+use crate::map::PadMotion;
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The version of the level format written by this version of the game. Files with a greater version are refused,
+/// older versions are read as long as they parse.
+///
+/// - Version 1 described the forces with `physics: (gravity, drag)`.
+/// - Version 2 replaced it with `environment` and `planet`, version 1 files are converted when loaded.
+pub const LEVEL_VERSION: u32 = 2;
+
+/// The Level struct describes a level, as written by designers in a RON file. Every field but the version has a
+/// default, so a level only needs to describe what differs from the endless game. For example:
+///
+/// ```ron
+/// (
+///     version: 2,
+///     name: "Twin peaks",
+///     planet: Mars,
+///     pads: [(x: 20, heigth: 15, width: 5), (x: 60, heigth: 25, width: 3, motion: Patrol(distance: 8, period: 160))],
+///     spawns: [(tick: 120, x: 40.0, heading: 90.0, speed: 0.4, size: 3.0)],
+///     win: LandOnEveryPad,
+/// )
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Level {
+    pub version: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub arena: ArenaSize,
+    /// The planet field sets the level on a planet, whose environment replaces the one of the environment field.
+    #[serde(default, with = "plain_option", skip_serializing_if = "Option::is_none")]
+    pub planet: Option<Planet>,
+    /// The environment field sets the gravity, the drag and the wind, see Level::environment.
+    #[serde(default)]
+    pub environment: Environment,
+    /// The physics field is the version 1 description of the forces, it is only read to convert older files.
+    #[serde(default, skip_serializing, deserialize_with = "plain_option::deserialize")]
+    pub physics: Option<Physics>,
+    /// The wells field lists the gravity wells of the level, see environment::GravityWell.
+    #[serde(default)]
+    pub wells: Vec<GravityWell>,
+    /// The terrain field is the height of the ground in each column, it is either empty or as wide as the arena.
+    #[serde(default)]
+    pub terrain: Vec<i32>,
+    #[serde(default)]
+    pub pads: Vec<PadDefinition>,
+    /// The spawns field lists the asteroids thrown by the level, in addition to the random ones.
+    #[serde(default)]
+    pub spawns: Vec<SpawnDefinition>,
+    /// The random_asteroids field keeps the endless game's random asteroids falling, their number still grows with
+    /// the score.
+    #[serde(default = "Level::default_random_asteroids")]
+    pub random_asteroids: bool,
+    /// The pickups field lets power-ups fall from time to time, see hazards::Pickup.
+    #[serde(default = "Level::default_pickups")]
+    pub pickups: bool,
+    /// The weapons field lets the ships shoot the asteroids, as in the arcade blaster mode.
+    #[serde(default)]
+    pub weapons: bool,
+    /// The craters field lets the asteroids dig craters in the ground and chip the pads they hit, see Arena::impact.
+    #[serde(default = "Level::default_craters")]
+    pub craters: bool,
+    #[serde(default)]
+    pub win: WinCondition,
+}
+
+/// The ArenaSize struct sets the size of the playing area, it can't be larger than the window.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ArenaSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Default for ArenaSize {
+    fn default() -> Self {
+        ArenaSize {
+            width: SCREEN_WIDTH,
+            height: SCREEN_HEIGHT,
+        }
+    }
+}
+
+/// The plain_option module reads and writes an optional field of a level without the `Some(...)` RON expects around
+/// its value, so that designers write `planet: Mars`. A missing field is None.
+mod plain_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer, T: Serialize>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        T::deserialize(deserializer).map(Some)
+    }
+}
+
+/// The Physics struct is how version 1 levels set the forces applied to the ship, a downwards gravity and a drag.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Physics {
+    pub gravity: f32,
+    pub drag: f32,
+}
+
+/// The PadDefinition struct places a landing pad, x is the column of its middle and the width should be odd. A pad
+/// with a motion moves around where it is placed, see map::PadMotion.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PadDefinition {
+    pub x: i32,
+    pub heigth: i32,
+    pub width: i32,
+    #[serde(default)]
+    pub motion: PadMotion,
+}
+
+/// The SpawnDefinition struct throws an asteroid at the given tick of the level. The heading is in degrees, 0 travels
+/// to the right and 90 falls straight down.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SpawnDefinition {
+    pub tick: u64,
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    pub heading: f32,
+    pub speed: f32,
+    pub size: f32,
+}
+
+/// The WinCondition enum tells when a level is won.
+///
+/// # Variants
+/// - Endless: The level can't be won, the game goes on until the ship is destroyed
+/// - Survive: The level is won once the ship survived the given number of ticks
+/// - Score: The level is won once the score reaches the given number of points
+/// - LandOnEveryPad: The level is won once the ship landed at least once on every pad
+/// - PadSequence: The level is won once the ship rested on every pad in the order they are listed, each one lights up
+///   in turn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum WinCondition {
+    #[default]
+    Endless,
+    Survive { ticks: u64 },
+    Score { points: usize },
+    LandOnEveryPad,
+    PadSequence,
+}
+
+/// The LevelError enum lists the reasons why a level can't be loaded.
+#[derive(Debug)]
+pub enum LevelError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The file is not a valid level, the line and column point at the error.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The file was written for a newer version of the game.
+    Version(u32),
+    /// The file is a valid level, but its content does not fit together, such as a pad outside of the arena.
+    Invalid(String),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(error) => write!(f, "{}", error),
+            LevelError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}: {}", line, column, message),
+            LevelError::Version(version) => write!(
+                f,
+                "level version {} is not supported, the latest version is {}",
+                version, LEVEL_VERSION
+            ),
+            LevelError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<std::io::Error> for LevelError {
+    fn from(error: std::io::Error) -> Self {
+        LevelError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for LevelError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        LevelError::Parse {
+            line: error.position.line,
+            column: error.position.col,
+            message: error.code.to_string(),
+        }
+    }
+}
+
+impl Default for Level {
+    /// The default level is an empty arena, with the endless game's physics and random asteroids.
+    fn default() -> Self {
+        Level {
+            version: LEVEL_VERSION,
+            name: String::new(),
+            arena: ArenaSize::default(),
+            planet: None,
+            environment: Environment::default(),
+            physics: None,
+            wells: Vec::new(),
+            terrain: Vec::new(),
+            pads: Vec::new(),
+            spawns: Vec::new(),
+            random_asteroids: true,
+            pickups: true,
+            weapons: false,
+            craters: true,
+            win: WinCondition::Endless,
+        }
+    }
+}
+
+impl Level {
+    /// The blaster function returns the arcade blaster mode: the endless game, where the ship shoots the asteroids
+    /// instead of only dodging them.
+    pub fn blaster() -> Level {
+        Level {
+            name: "Arcade blaster".to_string(),
+            weapons: true,
+            ..Level::default()
+        }
+    }
+
+    /// The sandbox function returns the orbital sandbox mode: a planetoid circled by asteroids in orbit and a black
+    /// hole, on the moon so that the wells' pull is what the ship feels the most. The random asteroids still fall,
+    /// and swing around the wells on their way down.
+    pub fn sandbox() -> Level {
+        let planetoid = GravityWell {
+            x: 40.0,
+            y: 22.0,
+            mass: 4.0,
+            radius: 4.0,
+            kind: WellKind::Planetoid,
+        };
+        let black_hole = GravityWell {
+            x: 72.0,
+            y: 40.0,
+            mass: 0.5,
+            radius: 1.0,
+            kind: WellKind::BlackHole,
+        };
+
+        // Each orbit starts above the planetoid, heading right at the orbital speed, so that it circles clockwise. The
+        // black hole slowly stretches the orbits until the asteroids fly off, new ones are put in orbit now and then.
+        let spawns = [
+            (9.0, 2.0, 0),
+            (13.0, 3.0, 90),
+            (11.0, 2.0, 390),
+            (9.0, 3.0, 900),
+            (13.0, 2.0, 1500),
+            (11.0, 3.0, 2100),
+        ]
+            .iter()
+            .map(|(distance, size, tick)| SpawnDefinition {
+                tick: *tick,
+                x: planetoid.x,
+                y: planetoid.y - distance,
+                heading: 0.0,
+                speed: planetoid.orbital_speed(*distance),
+                size: *size,
+            })
+            .collect();
+
+        Level {
+            name: "Orbital sandbox".to_string(),
+            planet: Some(Planet::Moon),
+            wells: vec![planetoid, black_hole],
+            spawns,
+            ..Level::default()
+        }
+    }
+
+    /// The load function reads and checks a level file, errors are prefixed with the path of the file.
+    pub fn load(path: &Path) -> Result<Level, String> {
+        fs::read_to_string(path)
+            .map_err(LevelError::from)
+            .and_then(|text| Level::parse(&text))
+            .map_err(|error| format!("{}:{}", path.display(), error))
+    }
+
+    /// The parse function reads a level from the content of a level file, and checks that it is playable.
+    pub fn parse(text: &str) -> Result<Level, LevelError> {
+        let mut level: Level = ron::from_str(text)?;
+        if level.version > LEVEL_VERSION {
+            return Err(LevelError::Version(level.version));
+        }
+        if let Some(physics) = level.physics.take() {
+            level.environment.gravity.y = physics.gravity;
+            level.environment.drag = physics.drag;
+        }
+        level.version = LEVEL_VERSION;
+        level.validate()?;
+        Ok(level)
+    }
+
+    /// The save function writes the level to a file, in the same format read by load.
+    pub fn save(&self, path: &Path) -> Result<(), LevelError> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| LevelError::Invalid(error.to_string()))?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// The environment function returns the forces of the level: the ones of its planet if it has one, its own otherwise.
+    pub fn environment(&self) -> Environment {
+        self.planet
+            .map(Planet::environment)
+            .unwrap_or(self.environment)
+    }
+
+    /// The validate function checks that everything described by the level fits in its arena.
+    pub fn validate(&self) -> Result<(), LevelError> {
+        let ArenaSize { width, height } = self.arena;
+        if !(1..=SCREEN_WIDTH).contains(&width) || !(1..=SCREEN_HEIGHT).contains(&height) {
+            return Err(LevelError::Invalid(format!(
+                "the arena is {}x{}, it must fit in the {}x{} window",
+                width, height, SCREEN_WIDTH, SCREEN_HEIGHT
+            )));
+        }
+        if !self.terrain.is_empty() && self.terrain.len() != width as usize {
+            return Err(LevelError::Invalid(format!(
+                "the terrain has {} columns, the arena is {} wide",
+                self.terrain.len(),
+                width
+            )));
+        }
+        if let Some(column) = self.terrain.iter().position(|h| *h < 0 || *h >= height) {
+            return Err(LevelError::Invalid(format!(
+                "the terrain's column {} is outside of the arena",
+                column
+            )));
+        }
+        for (index, pad) in self.pads.iter().enumerate() {
+            // A moving pad must fit in the arena wherever its motion takes it, its whole width included
+            let (reach_x, reach_heigth, min_width, period) = match pad.motion {
+                PadMotion::Static => (0, 0, pad.width, 1),
+                PadMotion::Patrol { distance, period } => (distance, 0, pad.width, period),
+                PadMotion::Elevator { distance, period } => (0, distance, pad.width, period),
+                PadMotion::Shrinking { min_width, period } => (0, 0, min_width, period),
+            };
+            let half = pad.width / 2;
+            if pad.x - reach_x - half < 0
+                || pad.x + reach_x + half >= width
+                || pad.heigth - reach_heigth < 1
+                || pad.heigth + reach_heigth >= height
+                || min_width < 1
+            {
+                return Err(LevelError::Invalid(format!(
+                    "pad {} does not fit in the arena",
+                    index + 1
+                )));
+            }
+            if reach_x < 0 || reach_heigth < 0 || min_width > pad.width || period == 0 {
+                return Err(LevelError::Invalid(format!(
+                    "pad {} has a motion with a negative distance, a width larger than the pad or no period",
+                    index + 1
+                )));
+            }
+        }
+        for (index, spawn) in self.spawns.iter().enumerate() {
+            if spawn.x < 0.0 || spawn.x >= width as f32 || spawn.size <= 0.0 {
+                return Err(LevelError::Invalid(format!(
+                    "spawn {} does not fit in the arena",
+                    index + 1
+                )));
+            }
+        }
+        for (index, well) in self.wells.iter().enumerate() {
+            let inside = (0.0..width as f32).contains(&well.x) && (0.0..height as f32).contains(&well.y);
+            if !inside || well.radius <= 0.0 || well.mass < 0.0 {
+                return Err(LevelError::Invalid(format!(
+                    "gravity well {} does not fit in the arena, or has a negative mass or no radius",
+                    index + 1
+                )));
+            }
+        }
+        let environment = self.environment();
+        if environment.drag < 0.0 || environment.wind.gust < 0.0 {
+            return Err(LevelError::Invalid(
+                "the drag and the gusts of the environment can't be negative".to_string(),
+            ));
+        }
+        let needs_pads = matches!(self.win, WinCondition::LandOnEveryPad | WinCondition::PadSequence);
+        if needs_pads && self.pads.is_empty() {
+            return Err(LevelError::Invalid(
+                "the level is won by landing on its pads, but it has no pad".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn default_random_asteroids() -> bool {
+        true
+    }
+
+    fn default_pickups() -> bool {
+        true
+    }
+
+    fn default_craters() -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The invalid function returns the message of the error validating the level, panicking if it is valid.
+    fn invalid(level: &Level) -> String {
+        match level.validate() {
+            Err(LevelError::Invalid(message)) => message,
+            other => panic!("expected an invalid level, got {:?}", other),
+        }
+    }
+
+    fn pad(x: i32, width: i32, motion: PadMotion) -> PadDefinition {
+        PadDefinition {
+            x,
+            heigth: 10,
+            width,
+            motion,
+        }
+    }
+
+    #[test]
+    fn parse_fills_the_defaults() {
+        let level = Level::parse("(version: 2, pads: [(x: 40, heigth: 10, width: 5)])").unwrap();
+        assert_eq!(level.arena.width, SCREEN_WIDTH);
+        assert_eq!(level.arena.height, SCREEN_HEIGHT);
+        assert_eq!(level.pads.len(), 1);
+        assert_eq!(level.pads[0].motion, PadMotion::Static);
+        assert!(level.random_asteroids && level.pickups && level.craters && !level.weapons);
+        assert_eq!(level.win, WinCondition::Endless);
+    }
+
+    #[test]
+    fn parse_converts_version_1_physics() {
+        let level = Level::parse("(version: 1, physics: (gravity: 0.05, drag: 0.2))").unwrap();
+        assert_eq!(level.version, LEVEL_VERSION);
+        assert_eq!(level.environment.gravity.y, 0.05);
+        assert_eq!(level.environment.drag, 0.2);
+        assert!(level.physics.is_none());
+    }
+
+    #[test]
+    fn parse_reads_a_plain_planet() {
+        let level = Level::parse("(version: 2, planet: Mars)").unwrap();
+        assert_eq!(level.planet, Some(Planet::Mars));
+        assert_eq!(level.environment(), Planet::Mars.environment());
+    }
+
+    #[test]
+    fn parse_refuses_newer_versions() {
+        let error = Level::parse(&format!("(version: {})", LEVEL_VERSION + 1)).unwrap_err();
+        assert!(matches!(error, LevelError::Version(version) if version == LEVEL_VERSION + 1));
+    }
+
+    #[test]
+    fn parse_points_at_syntax_errors() {
+        let error = Level::parse("(version: 2,\n  pads: [(x: 40,, heigth: 10)])").unwrap_err();
+        assert!(matches!(error, LevelError::Parse { line: 2, .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn saved_levels_parse_back() {
+        let level = Level {
+            planet: Some(Planet::Moon),
+            pads: vec![pad(40, 5, PadMotion::Patrol { distance: 8, period: 160 })],
+            win: WinCondition::PadSequence,
+            ..Level::default()
+        };
+        let text = ron::ser::to_string_pretty(&level, ron::ser::PrettyConfig::default()).unwrap();
+        let parsed = Level::parse(&text).unwrap();
+        assert_eq!(parsed.planet, level.planet);
+        assert_eq!(parsed.pads[0].motion, level.pads[0].motion);
+        assert_eq!(parsed.win, level.win);
+    }
+
+    #[test]
+    fn shipped_levels_are_valid() {
+        for name in ["red_dust", "twin_peaks"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("levels").join(format!("{}.ron", name));
+            Level::load(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn validate_refuses_arenas_larger_than_the_window() {
+        let level = Level {
+            arena: ArenaSize {
+                width: SCREEN_WIDTH + 1,
+                height: SCREEN_HEIGHT,
+            },
+            ..Level::default()
+        };
+        assert!(invalid(&level).contains("must fit"));
+    }
+
+    #[test]
+    fn validate_checks_the_terrain() {
+        let narrow = Level {
+            terrain: vec![0; 10],
+            ..Level::default()
+        };
+        assert!(invalid(&narrow).contains("columns"));
+
+        let mut heights = vec![0; SCREEN_WIDTH as usize];
+        heights[3] = SCREEN_HEIGHT;
+        let high = Level {
+            terrain: heights,
+            ..Level::default()
+        };
+        assert_eq!(invalid(&high), "the terrain's column 3 is outside of the arena");
+    }
+
+    #[test]
+    fn validate_includes_the_pads_half_width() {
+        let inside = Level {
+            pads: vec![pad(2, 5, PadMotion::Static), pad(SCREEN_WIDTH - 3, 5, PadMotion::Static)],
+            ..Level::default()
+        };
+        assert!(inside.validate().is_ok());
+
+        for x in [1, SCREEN_WIDTH - 2] {
+            let outside = Level {
+                pads: vec![pad(x, 5, PadMotion::Static)],
+                ..Level::default()
+            };
+            assert_eq!(invalid(&outside), "pad 1 does not fit in the arena");
+        }
+    }
+
+    #[test]
+    fn validate_checks_where_the_pads_move() {
+        let patrol = Level {
+            pads: vec![pad(10, 3, PadMotion::Patrol { distance: 10, period: 100 })],
+            ..Level::default()
+        };
+        assert_eq!(invalid(&patrol), "pad 1 does not fit in the arena");
+
+        let still = Level {
+            pads: vec![pad(40, 3, PadMotion::Elevator { distance: 4, period: 0 })],
+            ..Level::default()
+        };
+        assert!(invalid(&still).contains("no period"));
+
+        let growing = Level {
+            pads: vec![pad(40, 3, PadMotion::Shrinking { min_width: 5, period: 100 })],
+            ..Level::default()
+        };
+        assert!(invalid(&growing).contains("larger than the pad"));
+    }
+
+    #[test]
+    fn validate_needs_pads_to_land_on() {
+        let level = Level {
+            win: WinCondition::LandOnEveryPad,
+            ..Level::default()
+        };
+        assert!(invalid(&level).contains("has no pad"));
+    }
+}
@@ -0,0 +1,91 @@
+/// The achievements module handles the achievements, unlocked by playing and saved in the data directory
+pub mod achievements;
+
+/// The bindings module handles the key bindings, the key triggering each of the game's actions
+pub mod bindings;
+
+/// The character module handles everything related to the game's character, such as the character's ship position and momentum
+pub mod character;
+
+/// The control module handles the external control protocol, which lets another program fly the ship over
+/// stdin and stdout
+pub mod control;
+
+/// The daily module handles the daily challenge: the seed of the day, the recorded attempts and their export
+pub mod daily;
+
+/// The editor module handles the level editor, where levels are drawn with the mouse and test played
+pub mod editor;
+
+/// The env module handles the reinforcement learning environment, which lets agents play the game through a
+/// reset/step interface
+pub mod env;
+
+/// The environment module handles the forces the ships fly in: gravity, drag and wind, and the planets setting them
+pub mod environment;
+
+/// The ghost module handles the ghosts, recorded runs raced against in the time attack and the daily challenge
+pub mod ghost;
+
+/// The hazards module handles everything related to the game's hazards, such as the asteroids
+pub mod hazards;
+
+/// The i18n module handles the translations of the game's texts, read from the message catalogs of the locales
+/// directory
+pub mod i18n;
+
+/// The level module handles the level files, which describe the arena, its physics, its pads and its asteroids
+pub mod level;
+
+/// The map module handles everything related to the game's map, such as the landing pad
+pub mod map;
+
+/// The menu module handles the menus, navigated with the keyboard or the mouse
+pub mod menu;
+
+/// The net module handles the networked multiplayer, with an authoritative server and the clients connecting to it
+pub mod net;
+
+/// The options module handles the player's options, such as the theme, saved in the data directory
+pub mod options;
+
+/// The python module handles the Python bindings of the reinforcement learning environment
+#[cfg(feature = "python")]
+mod python;
+
+/// The save module handles the saved run, a game left before its end and continued from the main menu
+pub mod save;
+
+/// The statistics module handles the lifetime statistics, accumulated across games and saved in the data directory
+pub mod statistics;
+
+/// The storage module handles the files saved in the data directory, such as the achievements
+pub mod storage;
+
+/// The telemetry module handles the event bus, which sends the events of the games to sinks such as the event log
+pub mod telemetry;
+
+/// The theme module handles the themes, the palettes the game is drawn with, builtin or read from palette files
+pub mod theme;
+
+/// The time_attack module handles the pad-hopping time attack: its courses and the best time of each of them
+pub mod time_attack;
+
+/// The weapon module handles the ship's weapon, its projectiles and the fragments of the asteroids they destroy
+pub mod weapon;
+
+/// The world module handles the game's simulation, moving the ships and the asteroids without rendering anything
+pub mod world;
+
+/// The width of the window used in the game, the project uses bracket-lib's
+/// BTermBuilder to set the window size to 80x50
+pub const SCREEN_WIDTH: i32 = 80;
+
+/// The heigth of the window used in the game, the project uses bracket-lib's
+/// BTermBuilder to set the window size to 80x50
+pub const SCREEN_HEIGHT: i32 = 50;
+
+/// The duration of the frame in milliseconds, certain game actions are only
+/// performed after a certain amount of time has passed to avoid the game
+/// running too fast
+pub const FRAME_DURATION: f32 = 10.0;
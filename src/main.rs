@@ -1,44 +1,115 @@
 use bracket_lib::prelude::*;
+use game_project::achievements::{Achievements, ACHIEVEMENTS};
+use game_project::bindings::{Action, Bindings};
+use game_project::character::{Character, ShipInput};
+use game_project::control::StdioControl;
+use game_project::daily::{DailyResults, DailyRun};
+use game_project::editor::{self, Editor, EditorAction};
+use game_project::ghost::Ghost;
+use game_project::i18n::Catalog;
+use game_project::level::{Level, WinCondition};
+use game_project::menu::{Menu, MenuItem};
+use game_project::net;
+use game_project::options::Options;
+use game_project::save::SavedRun;
+use game_project::statistics::Statistics;
+use game_project::telemetry::{EventBus, JsonLinesSink};
+use game_project::theme::Theme;
+use game_project::time_attack::{self, Records};
+use game_project::world::{World, WorldEvent};
+use game_project::{FRAME_DURATION, SCREEN_HEIGHT, SCREEN_WIDTH};
+use std::collections::HashSet;
+use std::path::Path;
 
-/// The character module handles everything related to the game's character, such as the character's ship position and momentum
-mod character;
+/// The time an achievement's toast stays on screen, in milliseconds
+const TOAST_DURATION: f32 = 3000.0;
 
-/// The hazards module handles everything related to the game's hazards, such as the asteroids
-mod hazards;
+/// The time a fade from black into a new scene lasts, in milliseconds
+const FADE_DURATION: f32 = 250.0;
 
-/// The map module handles everything related to the game's map, such as the landing pad
-mod map;
+/// The time a popup stays in the arena, in milliseconds
+const POPUP_DURATION: f32 = 1000.0;
 
-/// The width of the window used in the game, the project uses bracket-lib's
-/// BTermBuilder to set the window size to 80x50
-const SCREEN_WIDTH: i32 = 80;
-
-/// The heigth of the window used in the game, the project uses bracket-lib's
-/// BTermBuilder to set the window size to 80x50
-const SCREEN_HEIGHT: i32 = 50;
-
-/// The duration of the frame in milliseconds, certain game actions are only
-/// performed after a certain amount of time has passed to avoid the game
-/// running too fast
-const FRAME_DURATION: f32 = 10.0;
+/// The Popup struct is a short text shown in the arena where something
+/// happened, such as the points scored by a near miss, it drifts up while
+/// it fades away
+struct Popup {
+    text: String,
+    x: f32,
+    y: f32,
+    remaining: f32,
+}
 
-/// Variant of gamemodes, This is used in the GameState::tick() implementation
-///  of the project, this is bracket-lib's way of handling the main game loop.
-/// This enumeration's role is to tell the main loop which game mode to run
-/// and display
+/// Variant of scenes, the game's screens. The scenes are stacked: the main
+/// loop runs the scene on top of the stack, and the scenes under it come back
+/// once it is popped. This is used in the GameState::tick() implementation of
+/// the project, this is bracket-lib's way of handling the main game loop.
 ///
 /// # Variants
-/// - Menu: The game's main menu with options to start playing or quit the game
-/// - Play: The game's main mode, where the player can control the character
-/// - Pause: The game's pause mode, where the game is paused and the player can
-///         resume playing or quit the game
-/// - Quit: The game's quit mode, where the game is about to close
-enum Gamemode {
+/// - Menu: The game's main menu with options to start playing or quit the game,
+///         it is always at the bottom of the stack
+/// - Play: The game's main scene, where the player can control the character
+/// - Pause: The game's pause overlay, where the game is paused and the player
+///         can resume playing, restart it or leave it for the main menu
+/// - Confirm: The game's confirmation overlay, asking the player whether they
+///         really want to do something that can't be undone
+/// - Controls: The game's controls screen, listing the keys of the actions
+/// - Lost: The game's lost scene, where the player's ship was destroyed
+/// - Won: The game's won scene, where the player met the level's win condition
+/// - Editor: The game's level editor, where levels are drawn with the mouse
+/// - Achievements: The game's achievements screen, listing the unlocked and locked achievements
+/// - Statistics: The game's statistics screen, showing what the player did across all their games
+/// - Options: The game's options screen, where the theme, the reduced motion and the language are chosen
+#[derive(Clone, Copy, PartialEq)]
+enum Scene {
     Menu,
     Play,
     Pause,
-    Quit,
+    Confirm(Confirmation),
+    Controls,
     Lost,
+    Won,
+    Editor,
+    Achievements,
+    Statistics,
+    Options,
+}
+
+impl Scene {
+    /// The is_overlay function tells whether the scene is drawn on top of the
+    /// scene under it, which stays on screen frozen, rather than replacing it
+    fn is_overlay(self) -> bool {
+        matches!(self, Scene::Pause | Scene::Confirm(_))
+    }
+}
+
+/// Variant of confirmations, the questions asked before doing something that
+/// can't be undone
+///
+/// # Variants
+/// - MainMenu: Leaving the game being played for the main menu
+/// - Quit: Quitting the game while playing
+#[derive(Clone, Copy, PartialEq)]
+enum Confirmation {
+    MainMenu,
+    Quit,
+}
+
+/// Variant of transitions, the changes of the scene stack asked for while a
+/// scene runs, they are made at the start of the next frame
+///
+/// # Variants
+/// - Push: Shows the scene on top of the current one
+/// - Pop: Goes back to the scene under the current one
+/// - Switch: Replaces the current scene by the given one
+/// - PopTo: Goes back to the given scene, leaving the scenes on top of it
+/// - Quit: Leaves every scene and closes the game
+enum Transition {
+    Push(Scene),
+    Pop,
+    Switch(Scene),
+    PopTo(Scene),
+    Quit,
 }
 
 /// The main struct of the game, this struct is used to store the game's state
@@ -46,33 +117,151 @@ enum Gamemode {
 /// game's components, suche as score, the position of the player character or
 /// of the asteroids
 struct State {
-    /// The current game mode, this is used to tell the main loop which game
-    /// mode to run and display
-    mode: Gamemode,
+    /// The scenes, this is the stack of the game's screens, the main loop
+    /// runs the one on top, the main menu is always at the bottom
+    scenes: Vec<Scene>,
+
+    /// The transition, this is the change of the scenes asked for during the
+    /// frame, it is made at the start of the next one
+    transition: Option<Transition>,
 
-    /// The character struct, this is used to store the player's character's
-    /// position and momentum
-    character: character::Character,
+    /// The fade, this is the time left in ms before the scene entered last
+    /// is fully shown, it comes out of black
+    fade: f32,
 
-    /// The asteroids vector, this is used to store the asteroids' position and
-    /// trajectory
-    asteroids: Vec<hazards::Asteroid>,
+    /// The world struct, this is used to store the game's simulation: the
+    /// player's character, the asteroids, the landing pads and the score
+    world: World,
+
+    /// The level, this is used to build the world every time the game starts,
+    /// it is the endless game unless a level file was given
+    level: Level,
 
     /// The frame time, this is used to keep track of the time passed since the
     /// last frame, this is used to avoid the game running too fast
     frame_time: f32,
 
-    /// The score, this is used to keep track of the player's score
-    score: usize,
+    /// The client, this is only set when playing online, in which case the
+    /// simulation is run by the server and the world is the client's copy of it
+    client: Option<net::Client>,
+
+    /// The control, this is only set when the ship is flown by an external
+    /// program through stdin and stdout instead of the keyboard
+    control: Option<StdioControl>,
+
+    /// The editor, this is set once the level editor was opened, it keeps the
+    /// level being edited while it is test played
+    editor: Option<Editor>,
+
+    /// The testing flag, this is set while the level being edited is test
+    /// played, the game then goes back to the editor instead of the menus
+    testing: bool,
+
+    /// The achievements, these are loaded from the data directory when the
+    /// game starts and saved every time one is unlocked
+    achievements: Achievements,
+
+    /// The toast, this is the message shown on top of the game when an
+    /// achievement is unlocked, with the time it stays on screen in ms
+    toast: Option<(String, f32)>,
+
+    /// The popups, these are the texts shown in the arena for a while where
+    /// the points of the near misses were scored
+    popups: Vec<Popup>,
+
+    /// The statistics, these are loaded from the data directory when the game
+    /// starts and saved at the end of every game
+    statistics: Statistics,
+
+    /// The recording flag, this is set while a game counting towards the
+    /// achievements and the statistics is played: a local game flown with the
+    /// keyboard, not a test play of the editor nor an externally controlled one
+    recording: bool,
+
+    /// The time attack seed, this is only set while playing a time attack
+    /// course, it is the seed the course was generated from
+    time_attack: Option<u64>,
+
+    /// The records, these are the best times of the time attack courses,
+    /// loaded from the data directory when the game starts
+    records: Records,
+
+    /// The new record flag, this is set when the last time attack run was
+    /// the best time of its course
+    new_record: bool,
+
+    /// The daily run, this is only set while playing the daily challenge, it
+    /// follows the inputs of the run to identify it
+    daily: Option<DailyRun>,
+
+    /// The daily results, these are the recorded attempts of the daily
+    /// challenge, loaded from the data directory when the game starts
+    daily_results: DailyResults,
+
+    /// The message, this is shown on the results screen, for example once the
+    /// daily challenge's result was exported
+    message: Option<String>,
+
+    /// The ghost, this is the recorded run raced against in the time attack
+    /// and the daily challenge, the personal best unless one was imported
+    ghost: Option<Ghost>,
+
+    /// The imported ghost, this is set when a ghost file was given on the
+    /// command line, it replaces the personal bests
+    imported_ghost: Option<Ghost>,
+
+    /// The run, this is the flight of the current game, recorded to become
+    /// the new ghost if it is a personal best
+    run: Ghost,
 
-    /// The avoided, this is used to keep track of the number of asteroids
-    /// avoided by the player, which in turn is used to update score once the
-    /// player avoided enough asteroids
-    avoided: usize,
+    /// The options, these are the player's choices of how the game looks,
+    /// loaded from the data directory when the game starts
+    options: Options,
 
-    /// The landing pad, this is used to store the landing pad's position and
-    /// heigth
-    landing_pad: map::LandingPad,
+    /// The theme, this is the theme chosen in the options, everything drawn
+    /// in the arena takes its colors from it
+    theme: Theme,
+
+    /// The catalog, this is the messages of the language chosen in the
+    /// options, every text shown on screen is read from it
+    catalog: Catalog,
+
+    /// The bindings, these are the keys triggering the game's actions, the
+    /// hints shown on screen name the keys bound here
+    bindings: Bindings,
+
+    /// The menu, this is the selection of the main menu, kept while the
+    /// other screens are shown so that the player comes back to it
+    menu: Menu,
+
+    /// The pause menu, this is the selection of the pause screen's menu, it
+    /// is reset every time the game is paused
+    pause_menu: Menu,
+
+    /// The events, this is the bus the events of the games played are sent
+    /// to after every step, it has no sink unless an event log was asked for
+    events: EventBus,
+
+    /// The keys, these are the keys pressed since the last frame in the order
+    /// they were pressed, read from bracket-lib's event queue so that quick
+    /// presses are not missed when several happen within a frame
+    keys: Vec<VirtualKeyCode>,
+
+    /// The confirm menu, this is the selection of the confirmation overlays'
+    /// yes and no, it is reset every time a confirmation is asked
+    confirm_menu: Menu,
+
+    /// The results menu, this is the selection of the menu shown once the
+    /// game is lost or won, it is reset at the end of every game
+    results_menu: Menu,
+
+    /// The saved run flag, this is set while a game left before its end is
+    /// saved in the data directory, it can then be continued from the menu
+    saved_run: bool,
+
+    /// The continued flag, this is set while the game played is the one of
+    /// the saved run, the saved run is removed once the game is over
+    continued: bool,
 }
 
 impl State {
@@ -91,135 +280,234 @@ impl State {
     /// let state = State::new();
     /// ```
     fn new() -> Self {
-        let mut random = RandomNumberGenerator::new();
-        let starting_point = random.range(0.0, SCREEN_WIDTH as f32);
-        let asteroids = vec![hazards::Asteroid::new(starting_point, 0.0)];
-
+        let options = Options::load();
         State {
-            mode: Gamemode::Menu,
-            character: character::Character::new((SCREEN_WIDTH / 2) as f32, SCREEN_HEIGHT as f32),
+            scenes: vec![Scene::Menu],
+            transition: None,
+            fade: 0.0,
+            world: World::new(1),
+            level: Level::default(),
             frame_time: 0.0,
-            asteroids,
-            score: 0,
-            avoided: 0,
-            landing_pad: map::LandingPad::new(random.range(0, SCREEN_WIDTH)),
+            client: None,
+            control: None,
+            editor: None,
+            testing: false,
+            achievements: Achievements::load(),
+            toast: None,
+            popups: Vec::new(),
+            statistics: Statistics::load(),
+            recording: false,
+            time_attack: None,
+            records: Records::load(),
+            new_record: false,
+            daily: None,
+            daily_results: DailyResults::load(),
+            message: None,
+            ghost: None,
+            imported_ghost: None,
+            run: Ghost::default(),
+            theme: options.theme(),
+            catalog: Catalog::new(options.language),
+            bindings: options.key_bindings(),
+            menu: Menu::default(),
+            pause_menu: Menu::default(),
+            confirm_menu: Menu::default(),
+            keys: Vec::new(),
+            events: EventBus::default(),
+            results_menu: Menu::default(),
+            saved_run: SavedRun::load().is_some(),
+            continued: false,
+            options,
         }
     }
 
     /// The main menu function, this is used to display the game's main menu
-    /// while the state's mode is set to Menu, from there the player can start
-    /// playing or quit the game
+    /// while the Menu scene runs, from there the player can start
+    /// playing or quit the game, the other game modes are in a submenu
     /// The function is only called by the GameState::tick() function.
     fn main_menu(&mut self, ctx: &mut BTerm) {
         ctx.cls();
-        ctx.printer(
-            SCREEN_WIDTH / 2,
-            20,
-            "#[orange]RUST ROVER#[]",
-            TextAlign::Center,
-            Some(RGBA::from_u8(200, 0, 0, 255)),
-        );
-        ctx.print_centered(23, "Press (P) to start playing !");
-        ctx.print_centered(24, "Press (Q) to quit the game");
+        ctx.print_color_centered(20, self.theme.title, self.theme.title_background, "RUST ROVER");
+        let modes = vec![
+            self.menu_item("menu.blaster", Action::Blaster),
+            self.menu_item("menu.time_attack", Action::TimeAttack),
+            self.menu_item("menu.daily", Action::Daily),
+            self.menu_item("menu.sandbox", Action::Sandbox),
+        ];
+        let items = [
+            self.menu_item("menu.continue", Action::Continue).enabled(self.saved_run),
+            self.menu_item("menu.play", Action::Play),
+            MenuItem::submenu(self.catalog.text("menu.modes"), modes),
+            self.menu_item("menu.editor", Action::Editor),
+            self.menu_item("menu.achievements", Action::Achievements),
+            self.menu_item("menu.statistics", Action::Statistics),
+            self.menu_item("menu.options", Action::Options),
+            self.menu_item("hint.quit", Action::Quit),
+        ];
+        let action = self.menu.update(ctx, &self.keys, &self.bindings, &items, 23);
+        self.menu.render(ctx, &items, 23, &self.theme);
 
-        if let Some(key) = ctx.key {
-            match key {
-                VirtualKeyCode::P => self.restart(),
-                VirtualKeyCode::Q => self.quit(ctx),
-                _ => {}
+        match action {
+            Some(Action::Continue) => self.continue_run(),
+            Some(Action::Play) => self.restart(),
+            Some(Action::Blaster) => {
+                self.level = Level::blaster();
+                self.time_attack = None;
+                self.daily = None;
+                self.restart();
             }
+            Some(Action::Sandbox) => {
+                self.level = Level::sandbox();
+                self.time_attack = None;
+                self.daily = None;
+                self.restart();
+            }
+            Some(Action::Daily) => self.start_daily(),
+            Some(Action::TimeAttack) => {
+                let seed = RandomNumberGenerator::new().range(0, 100_000) as u64;
+                self.start_time_attack(seed);
+            }
+            Some(Action::Editor) => self.open_editor(Path::new(editor::DEFAULT_LEVEL_PATH)),
+            Some(Action::Achievements) => self.change(Transition::Push(Scene::Achievements)),
+            Some(Action::Statistics) => self.change(Transition::Push(Scene::Statistics)),
+            Some(Action::Options) => self.change(Transition::Push(Scene::Options)),
+            Some(Action::Quit) => self.change(Transition::Quit),
+            _ => {}
         }
     }
 
-    /// The play function, this is used to display the game's main mode, while
-    /// the state's mode is set to Play, from there the player can control the
-    /// character and avoid the asteroids while trying to land on the landing
-    /// pad
+    /// The hint function, this is used to get the message of the given key
+    /// in the chosen language, naming the key bound to the given action
+    fn hint(&self, message: &str, action: Action) -> String {
+        self.catalog.hint(message, &self.bindings, action)
+    }
+
+    /// The pressed function, this is used to know whether the key bound to
+    /// the given action was pressed since the last frame
+    fn pressed(&self, action: Action) -> bool {
+        self.keys.iter().any(|key| self.bindings.is(*key, action))
+    }
+
+    /// The menu_item function, this is used to build a menu item triggering
+    /// the given action, labelled with the hint of the given message
+    fn menu_item(&self, message: &str, action: Action) -> MenuItem {
+        MenuItem::new(self.hint(message, action), action)
+    }
+
+    /// The play function, this is used to run the game's main scene, from
+    /// there the player can control the character and avoid the asteroids
+    /// while trying to land on the landing pad
     fn play(&mut self, ctx: &mut BTerm) {
-        ctx.cls(); // Clear the screen
-        ctx.print(0, 1, format!("Score: {}", self.score)); // Print the score
+        if self.client.is_some() {
+            self.play_online(ctx);
+            return;
+        }
 
         let input = INPUT.lock(); // Get the input state to check for key presses
         self.frame_time += ctx.frame_time_ms; // Add the time passed in ms between the main loop iterations to the frame_time
+        if self.recording {
+            self.statistics.flight_time += ctx.frame_time_ms as f64 / 1000.0;
+        }
 
-        // Check for menu key presses (Not gameplay-related) to pause or quit the game
-        if let Some(key) = ctx.key {
-            match key {
-                VirtualKeyCode::P => self.mode = Gamemode::Pause,
-                VirtualKeyCode::Q => self.mode = Gamemode::Quit,
-                _ => {}
-            }
+        // Check for menu key presses (Not gameplay-related) to pause or quit the game, or to stop test playing
+        if self.pressed(Action::Pause) {
+            self.change(Transition::Push(Scene::Pause));
+        } else if self.pressed(Action::Quit) {
+            self.change(Transition::Push(Scene::Confirm(Confirmation::Quit)));
+        } else if self.testing && self.pressed(Action::TestPlay) {
+            self.change(Transition::Pop);
         }
 
         // All the following actions only occurs if the frame_time is greater than the FRAME_DURATION, so these events speed are kept in check
-        // by the FRAME_DURATION constant.
-        if self.frame_time > FRAME_DURATION {
-            // Check for key presses to control the character's ship, thrusting in the corresponding direction, with the use of event_queue
-            // it is possible to move in multiple directions at once
-            input.key_pressed_set().iter().for_each(|&key| match key {
-                VirtualKeyCode::Up => self.character.thrust(character::Direction::Up, 0.42, 1.0),
-                VirtualKeyCode::Left => self.character.thrust(character::Direction::Left, 0.3, 1.0),
-
-                VirtualKeyCode::Right => {
-                    self.character.thrust(character::Direction::Right, 0.3, 1.0);
-                }
-                _ => {}
-            });
-
-            // Apply gravity and drag to the character's ship, then apply momentum to the ship
-            self.character.apply_gravity_and_drag(0.4, 0.2);
-            self.character.apply_momentum();
-
-            // Update the asteroids' position and check for collisions
-            self.asteroids
-                .iter_mut()
-                .for_each(|asteroid| asteroid.update());
+        // by the FRAME_DURATION constant. The world isn't stepped in the frame the game is paused or left in.
+        if self.frame_time > FRAME_DURATION && self.transition.is_none() {
+            // The thrusters to fire are read from the external controller if there is one, from the pressed keys otherwise
+            let ship_input = match self.control.as_mut() {
+                Some(control) => match control.exchange(&self.world) {
+                    Ok(Some(ship_input)) => ship_input,
+                    // The controller went away, there is nobody left to fly the ship
+                    _ => {
+                        self.change(Transition::Quit);
+                        return;
+                    }
+                },
+                None => State::ship_input(&self.bindings, input.key_pressed_set()),
+            };
 
-            // Check if the number of asteroid is less than the score, if so, add a new asteroid to the game, this is the project's
-            // way of increasing the difficulty as the player's score increases
-            let asteroid_number = self.asteroids.len();
-            if asteroid_number < self.score {
-                let mut random = RandomNumberGenerator::new();
-                let starting_point = random.range(1.0, SCREEN_WIDTH as f32 - 1.0);
-                self.asteroids
-                    .push(hazards::Asteroid::new(starting_point, 0.0));
+            // The inputs of a daily challenge are hashed, the hash identifies the run in the exported result
+            if let Some(daily) = self.daily.as_mut() {
+                daily.replay.write_input(&ship_input);
             }
 
+            // Fly the character's ship, move the asteroids and check for collisions
+            self.world.step(&[ship_input]);
+            self.events.publish(&self.world);
             self.frame_time = 0.0;
-        }
-
-        // Keep the character's ship inside the window
-        self.character.coordinate.x =
-            State::keep_in_bounds(self.character.coordinate.x, 0, SCREEN_WIDTH - 1);
-        self.character.coordinate.y =
-            State::keep_in_bounds(self.character.coordinate.y, 1, SCREEN_HEIGHT);
+            self.run.record(&self.world.characters[0]);
+            self.add_popups();
 
-        // Check if the character's ship has landed on the landing pad
-        if self.landing_pad.check_landing(&mut self.character) {
-            self.character.coordinate.y = (SCREEN_HEIGHT - self.landing_pad.heigth) as f32;
+            if self.recording {
+                self.check_achievements();
+                for event in &self.world.events {
+                    self.statistics.observe(event);
+                }
+            }
         }
 
-        // Check for asteroid which left the screen and increase the avoided counter, if the avoided counter is greater than the number of
-        // asteroid, increase the score and reset the avoided counter
-        self.asteroids.iter_mut().for_each(|asteroid| {
-            if asteroid.check_finished_course(SCREEN_WIDTH, SCREEN_HEIGHT) {
-                let mut random = RandomNumberGenerator::new();
-                let starting_point = random.range(1.0, SCREEN_WIDTH as f32 - 1.0);
-                *asteroid = hazards::Asteroid::new(starting_point, 0.0);
-                self.avoided += 1;
+        // Once the character's ship was destroyed by an asteroid, the game goes to the Lost scene, an external
+        // controller is sent the final state of the world and the game closes. A test play goes straight back to the
+        // editor once it is over
+        if self.world.is_over() {
+            self.change(Transition::Switch(Scene::Lost));
+            if let Some(control) = self.control.as_mut() {
+                let _ = control.report(&self.world);
+                self.change(Transition::Quit);
             }
-        });
-        if self.avoided > self.asteroids.len() {
-            self.score += 1;
-            self.avoided = 0;
+        } else if self.world.is_won() {
+            self.change(Transition::Switch(Scene::Won));
+        }
+        if self.testing && (self.world.is_over() || self.world.is_won()) {
+            self.change(Transition::Pop);
         }
 
-        // Check for collision between the character's ship and the asteroids, if so, set the game's mode to Menu
-        self.asteroids.iter_mut().for_each(|asteroid| {
-            if asteroid.check_collision(&mut self.character) {
-                self.mode = Gamemode::Lost;
+        // The last unlocked achievement is shown on top of the game for a while, and so are the popups
+        if let Some((_, remaining)) = self.toast.as_mut() {
+            *remaining -= ctx.frame_time_ms;
+            if *remaining <= 0.0 {
+                self.toast = None;
             }
-        });
+        }
+        for popup in self.popups.iter_mut() {
+            popup.remaining -= ctx.frame_time_ms;
+        }
+        self.popups.retain(|popup| popup.remaining > 0.0);
+
+        self.render_play(ctx);
+    }
+
+    /// The render_play function, this is used to draw the game being played:
+    /// the score and the power-ups, the ghost, the world and the toast
+    fn render_play(&mut self, ctx: &mut BTerm) {
+        ctx.cls(); // Clear the screen
+        ctx.print(0, 1, self.catalog.format("play.score", &[("score", &self.world.score)])); // Print the score
+        State::print_power_ups(
+            ctx,
+            &self.theme,
+            &self.catalog,
+            self.world.characters.first(),
+            self.world.slow_time,
+        );
+        if self.world.win == WinCondition::PadSequence {
+            self.print_splits(ctx);
+        }
+        if self.testing {
+            ctx.print(0, 2, self.hint("play.test", Action::TestPlay));
+        }
+        let combo = self.world.characters.first().map_or(1, |character| character.combo);
+        if combo > 1 {
+            let combo = self.catalog.format("play.combo", &[("combo", &combo)]);
+            ctx.print_color(0, 4, self.theme.highlight, BLACK, combo);
+        }
 
         // Clears the fancy console (The console which contains fancy-rendred element such as the character's ship and the asteroids) to
         // prepare for the next frame.
@@ -227,33 +515,288 @@ impl State {
         ctx.cls();
         ctx.set_active_console(0);
 
-        // Render the character's ship, the asteroids and the landing pad
-        self.character.render(ctx);
+        // Render the ghost behind everything else, then the character's ship, the asteroids and the landing pad
+        if let Some(ghost) = self.ghost.as_ref() {
+            ghost.render(ctx, self.world.tick, &self.theme);
+            let label = self.catalog.format("play.ghost", &[("label", &ghost.label)]);
+            ctx.print_color(0, 6, self.theme.ghost, BLACK, label);
+        }
+        self.world.render(ctx, &self.theme, &self.catalog);
+        self.render_popups(ctx);
 
-        self.asteroids
-            .iter_mut()
-            .for_each(|asteroid| asteroid.render(ctx));
+        if let Some((message, _)) = self.toast.as_ref() {
+            ctx.print_color_centered(4, self.theme.highlight, BLACK, message.as_str());
+        }
+    }
 
-        self.landing_pad.render(ctx);
+    /// The add_popups function, this is used to show the points scored by
+    /// the near misses of the player's ship during the last step where the
+    /// asteroids passed by
+    fn add_popups(&mut self) {
+        for event in &self.world.events {
+            if let WorldEvent::NearMiss { ship: 0, x, y, points, combo, .. } = event {
+                self.popups.push(Popup {
+                    text: self
+                        .catalog
+                        .format("play.near_miss", &[("points", points), ("combo", combo)]),
+                    x: *x,
+                    y: *y,
+                    remaining: POPUP_DURATION,
+                });
+            }
+        }
     }
 
-    /// The pause function, this is used to display the game's pause mode, while
-    /// in pause mode the game clears the screen and waits for input from the
-    /// player to resume playing or quit the game
-    fn pause(&mut self, ctx: &mut BTerm) {
+    /// The render_popups function, this is used to draw the popups over the
+    /// arena, they drift up a few cells unless the motion is reduced
+    fn render_popups(&self, ctx: &mut BTerm) {
+        for popup in &self.popups {
+            let rise = if self.theme.reduced_motion {
+                0.0
+            } else {
+                3.0 * (1.0 - popup.remaining / POPUP_DURATION)
+            };
+            let width = popup.text.chars().count() as i32;
+            let x = (popup.x as i32 - width / 2).clamp(0, (SCREEN_WIDTH - width).max(0));
+            let y = ((popup.y - rise) as i32).clamp(0, SCREEN_HEIGHT - 1);
+            ctx.print_color(x, y, self.theme.highlight, BLACK, &popup.text);
+        }
+    }
+
+    /// The check_achievements function, this is used to go through the events
+    /// of the last step to unlock achievements, the achievements are saved as
+    /// soon as one is unlocked so that it is not lost if the game crashes
+    fn check_achievements(&mut self) {
+        let mut unlocked = Vec::new();
+        for event in &self.world.events {
+            unlocked.extend(self.achievements.observe(event));
+        }
+        if let Some(achievement) = unlocked.last() {
+            let name = self
+                .catalog
+                .lookup(&format!("achievement.{}.name", achievement.id))
+                .unwrap_or(achievement.name);
+            self.toast = Some((
+                self.catalog.format("play.achievement", &[("name", &name)]),
+                TOAST_DURATION,
+            ));
+            self.achievements.save();
+        }
+    }
+
+    /// The play_online function replaces the play function when connected to a
+    /// server, the inputs are sent to the server which runs the simulation, and
+    /// the world received in return is rendered
+    fn play_online(&mut self, ctx: &mut BTerm) {
+        // Only quitting is possible online, the server keeps running for the other players
+        let quit = self.pressed(Action::Quit);
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+
+        ctx.cls();
+        client.receive();
+
+        // An input is sent for every server tick, the server flies the ship with one per tick as well. A long frame
+        // catches up with a few ticks at most
+        let input = INPUT.lock();
+        let tick = net::SERVER_TICK.as_secs_f32() * 1000.0;
+        self.frame_time = (self.frame_time + ctx.frame_time_ms).min(tick * 4.0);
+        while self.frame_time >= tick {
+            client.send_input(State::ship_input(&self.bindings, input.key_pressed_set()));
+            self.frame_time -= tick;
+        }
+        client.interpolate();
+
+        let catalog = &self.catalog;
+        ctx.print(0, 1, catalog.format("play.score", &[("score", &client.world.score)]));
+        ctx.print(
+            0,
+            2,
+            catalog.format("online.players", &[("players", &client.world.characters.len())]),
+        );
+        State::print_power_ups(ctx, &self.theme, catalog, client.own_character(), client.world.slow_time);
+        match client.own_character() {
+            None => ctx.print_centered(20, catalog.text("online.connecting")),
+            Some(character) if character.destroyed => {
+                ctx.print_centered(20, catalog.text("online.destroyed"));
+                ctx.print_centered(22, catalog.text("online.waiting"));
+            }
+            Some(_) => {}
+        }
+
         ctx.set_active_console(1);
         ctx.cls();
         ctx.set_active_console(0);
+
+        client.world.render(ctx, &self.theme, &self.catalog);
+        if quit {
+            self.change(Transition::Quit);
+        }
+    }
+
+    /// The print_power_ups function, this is used to show the power-ups still
+    /// active on the player's ship, with the seconds they still last (the
+    /// simulation runs at about 60 ticks per second)
+    fn print_power_ups(
+        ctx: &mut BTerm,
+        theme: &Theme,
+        catalog: &Catalog,
+        character: Option<&Character>,
+        slow_time: u64,
+    ) {
+        let mut power_ups = Vec::new();
+        if let Some(character) = character.filter(|character| character.shield > 0) {
+            power_ups.push(catalog.format("play.shield", &[("seconds", &(character.shield / 60 + 1))]));
+        }
+        if slow_time > 0 {
+            power_ups.push(catalog.format("play.slow_time", &[("seconds", &(slow_time / 60 + 1))]));
+        }
+        if !power_ups.is_empty() {
+            ctx.print_color(0, 3, theme.shield, BLACK, power_ups.join("  "));
+        }
+    }
+
+    /// The print_splits function, this is used to show the progress through a
+    /// pad sequence: the pad to reach, the running time and the last split
+    fn print_splits(&self, ctx: &mut BTerm) {
+        let pads = self.world.arena.landing_pads.len();
+        let Some(leader) = self.world.leader() else {
+            return;
+        };
+        let mut line = self.catalog.format(
+            "play.splits",
+            &[
+                ("pad", &(leader.target + 1).min(pads)),
+                ("pads", &pads),
+                ("time", &time_attack::format_time(self.world.tick)),
+            ],
+        );
+        if let Some(split) = leader.splits.last() {
+            line += "  ";
+            line += &self
+                .catalog
+                .format("play.last_split", &[("time", &time_attack::format_time(*split))]);
+        }
+        ctx.print_color(0, 5, self.theme.highlight, BLACK, line);
+    }
+
+    /// The ship_input function translates the pressed keys into the
+    /// thrusters to fire and a shot, the arrow keys and the space bar unless
+    /// they were rebound, with the use of event_queue it is possible to move
+    /// in multiple directions at once
+    fn ship_input(bindings: &Bindings, keys: &HashSet<VirtualKeyCode>) -> ShipInput {
+        ShipInput {
+            up: bindings.held(keys, Action::Thrust),
+            left: bindings.held(keys, Action::Left),
+            right: bindings.held(keys, Action::Right),
+            fire: bindings.held(keys, Action::Fire),
+        }
+    }
+
+    /// The pause function, this is used to run the pause overlay, the game
+    /// stays frozen under the pause menu, from which the player can resume
+    /// playing, restart the game, change the options, see the controls or go
+    /// back to the main menu once they confirmed it
+    fn pause(&mut self, ctx: &mut BTerm) {
+        let items = self.pause_items();
+        match self.pause_menu.update(ctx, &self.keys, &self.bindings, &items, 22) {
+            Some(Action::Pause | Action::Back) => self.change(Transition::Pop),
+            Some(Action::Restart) => self.restart(),
+            Some(Action::Options) => self.change(Transition::Push(Scene::Options)),
+            Some(Action::Controls) => self.change(Transition::Push(Scene::Controls)),
+            Some(Action::MainMenu) => self.change(Transition::Push(Scene::Confirm(Confirmation::MainMenu))),
+            _ => {}
+        }
+        self.render_pause(ctx);
+    }
+
+    /// The pause_items function, this is used to build the items of the
+    /// pause menu
+    fn pause_items(&self) -> [MenuItem; 5] {
+        [
+            self.menu_item("pause.resume", Action::Pause),
+            self.menu_item("pause.restart", Action::Restart),
+            self.menu_item("menu.options", Action::Options),
+            self.menu_item("pause.controls", Action::Controls),
+            self.menu_item("pause.main_menu", Action::MainMenu),
+        ]
+    }
+
+    /// The render_pause function, this is used to draw the pause menu in a
+    /// box over the frozen game, it is also used to draw it frozen under a
+    /// confirmation
+    fn render_pause(&self, ctx: &mut BTerm) {
+        ctx.draw_box(10, 17, 59, 11, WHITE, BLACK);
+        ctx.print_color_centered(19, self.theme.title, BLACK, self.catalog.text("pause.title"));
+        self.pause_menu.render(ctx, &self.pause_items(), 22, &self.theme);
+    }
+
+    /// The render_frozen function, this is used to draw the game being played
+    /// dimmed and without its texts, under the pause menu
+    fn render_frozen(&mut self, ctx: &mut BTerm) {
         ctx.cls();
-        ctx.print_centered(20, "Game Paused");
-        ctx.print_centered(23, "Press (P) to resume playing !");
-        ctx.print_centered(24, "Press (Q) to quit the game");
-        if let Some(key) = ctx.key {
-            match key {
-                VirtualKeyCode::P => self.mode = Gamemode::Play,
-                VirtualKeyCode::Q => self.mode = Gamemode::Quit,
-                _ => {}
-            }
+        let theme = self.theme.dimmed();
+        if let Some(ghost) = self.ghost.as_ref() {
+            ghost.render(ctx, self.world.tick, &theme);
+        }
+        self.world.render(ctx, &theme, &self.catalog);
+    }
+
+    /// The confirm function, this is used to run a confirmation overlay, the
+    /// question is asked in a box over the scene under it, the action is
+    /// only carried out once the player answered yes
+    fn confirm(&mut self, confirmation: Confirmation, ctx: &mut BTerm) {
+        let items = [
+            self.menu_item("confirm.yes", Action::Confirm),
+            self.menu_item("confirm.no", Action::Back),
+        ];
+        match self.confirm_menu.update(ctx, &self.keys, &self.bindings, &items, 25) {
+            Some(Action::Confirm) => match confirmation {
+                Confirmation::MainMenu => self.change(Transition::PopTo(Scene::Menu)),
+                Confirmation::Quit => self.change(Transition::Quit),
+            },
+            Some(Action::Back) => self.change(Transition::Pop),
+            _ => {}
+        }
+
+        let question = match confirmation {
+            Confirmation::MainMenu if self.saveable() => "confirm.main_menu_saved",
+            Confirmation::MainMenu => "confirm.main_menu",
+            Confirmation::Quit if self.saveable() => "confirm.quit_saved",
+            Confirmation::Quit => "confirm.quit",
+        };
+        ctx.draw_box(10, 20, 59, 8, self.theme.highlight, BLACK);
+        ctx.print_centered(22, self.catalog.text(question));
+        self.confirm_menu.render(ctx, &items, 25, &self.theme);
+    }
+
+    /// The controls_screen function, this is used to list the keys bound to
+    /// the actions of the game while the Controls scene runs
+    fn controls_screen(&mut self, ctx: &mut BTerm) {
+        ctx.cls();
+        let catalog = &self.catalog;
+        ctx.print_color_centered(10, self.theme.title, BLACK, catalog.text("controls.title"));
+        let actions = [
+            Action::Thrust,
+            Action::Left,
+            Action::Right,
+            Action::Fire,
+            Action::Pause,
+            Action::Quit,
+            Action::MenuUp,
+            Action::MenuDown,
+            Action::Select,
+            Action::Back,
+        ];
+        for (index, action) in actions.iter().enumerate() {
+            let y = 13 + index as i32;
+            ctx.print(20, y, catalog.text(&format!("action.{:?}", action)));
+            ctx.print_color(48, y, self.theme.highlight, BLACK, catalog.key(self.bindings.key(*action)));
+        }
+        ctx.print_centered(15 + actions.len() as i32, self.hint("hint.back", Action::Back));
+        if self.pressed(Action::Back) {
+            self.change(Transition::Pop);
         }
     }
 
@@ -263,70 +806,665 @@ impl State {
     /// restart the game or quit the game
     fn lost(&mut self, ctx: &mut BTerm) {
         ctx.cls();
-        ctx.print_centered(20, "You lost !");
-        ctx.print_centered(22, format!("Your score is : {}", self.score));
-        ctx.print_centered(24, "Press (P) to restart the game");
-        ctx.print_centered(25, "Press (Q) to quit the game");
-        if let Some(key) = ctx.key {
-            match key {
-                VirtualKeyCode::P => self.restart(),
-                VirtualKeyCode::Q => self.mode = Gamemode::Quit,
-                _ => {}
+        let catalog = &self.catalog;
+        ctx.print_centered(20, catalog.text("lost.title"));
+        ctx.print_centered(22, catalog.format("result.score", &[("score", &self.world.score)]));
+        let mut items = vec![self.menu_item("lost.restart", Action::Play)];
+
+        // The daily challenge shows the day's recorded result, which can be exported to be shared
+        let today = self.daily.as_ref().map(|daily| daily.date.clone());
+        let mut top = 24;
+        if let Some(date) = today.as_deref() {
+            ctx.print_centered(24, catalog.format("daily.title", &[("date", &date)]));
+            let recorded = self.daily_results.results.get(date);
+            match recorded {
+                Some(result) => ctx.print_centered(25, catalog.format("daily.recorded", &[("score", &result.score)])),
+                None => ctx.print_centered(25, catalog.text("daily.not_recorded")),
             }
+            items.push(self.menu_item("daily.export", Action::Export).enabled(recorded.is_some()));
+            top = 27;
+        }
+        items.push(self.menu_item("hint.quit", Action::Quit));
+        if let Some(message) = self.message.as_deref() {
+            ctx.print_color_centered(top + items.len() as i32 + 1, self.theme.highlight, BLACK, message);
+        }
+
+        let action = self.results_menu.update(ctx, &self.keys, &self.bindings, &items, top);
+        self.results_menu.render(ctx, &items, top, &self.theme);
+        match action {
+            Some(Action::Play) => self.restart(),
+            Some(Action::Quit) => self.change(Transition::Quit),
+            Some(Action::Export) => {
+                if let Some(date) = today.as_deref() {
+                    self.message = Some(match self.daily_results.export(date) {
+                        Ok(path) => self.catalog.format("daily.exported", &[("path", &path.display())]),
+                        Err(error) => self.catalog.format("daily.export_failed", &[("error", &error)]),
+                    });
+                }
+            }
+            _ => {}
         }
     }
 
-    /// The quit function, this is used to quit the game using the main loop's
-    /// quitting flag
-    fn quit(&mut self, ctx: &mut BTerm) {
+    /// The won function, this is used to display the game's won mode, this
+    /// occurs when the level's win condition is met, the game clears the screen
+    /// and waits for input from the player to play the level again or quit
+    fn won(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(1);
+        ctx.cls();
+        ctx.set_active_console(0);
         ctx.cls();
-        ctx.quitting = true;
+        let catalog = &self.catalog;
+        ctx.print_centered(20, catalog.text("won.title"));
+        let mut y = 22;
+        match self.time_attack {
+            Some(seed) => {
+                ctx.print_centered(y, catalog.format("won.course", &[("seed", &seed)]));
+                let splits = self.world.leader().map_or(&[][..], |leader| &leader.splits);
+                for (index, split) in splits.iter().enumerate() {
+                    y += 1;
+                    ctx.print_centered(
+                        y,
+                        catalog.format(
+                            "won.split",
+                            &[("pad", &(index + 1)), ("time", &time_attack::format_time(*split))],
+                        ),
+                    );
+                }
+                y += 2;
+                if self.new_record {
+                    ctx.print_color_centered(y, self.theme.highlight, BLACK, catalog.text("won.new_best"));
+                } else if let Some(best) = self.records.best.get(&seed) {
+                    ctx.print_centered(
+                        y,
+                        catalog.format("won.best", &[("time", &time_attack::format_time(best.total()))]),
+                    );
+                }
+            }
+            None => ctx.print_centered(y, catalog.format("result.score", &[("score", &self.world.score)])),
+        }
+        let items = [
+            self.menu_item("won.again", Action::Play),
+            self.menu_item("hint.quit", Action::Quit),
+        ];
+        let action = self.results_menu.update(ctx, &self.keys, &self.bindings, &items, y + 2);
+        self.results_menu.render(ctx, &items, y + 2, &self.theme);
+        match action {
+            Some(Action::Play) => self.restart(),
+            Some(Action::Quit) => self.change(Transition::Quit),
+            _ => {}
+        }
+    }
+
+    /// The end_game function, this is used to count the game that just ended
+    /// in the statistics and the time attack records and to save them, a game
+    /// that is not recorded is not counted
+    fn end_game(&mut self) {
+        if !self.recording {
+            return;
+        }
+        self.recording = false;
+        if self.continued {
+            self.continued = false;
+            self.saved_run = false;
+            SavedRun::remove();
+        }
+        self.statistics
+            .finish_game(Statistics::mode(&self.level.name), self.world.score);
+        self.statistics.save();
+
+        // Only the first attempt of the day's challenge is recorded, but the best one is kept as the day's ghost
+        if let Some(daily) = self.daily.as_ref() {
+            self.daily_results.record(daily, self.world.score);
+            if daily.ranked {
+                self.daily_results.save();
+            }
+            let name = Ghost::daily_name(&daily.date);
+            if Ghost::load(&name).is_none_or(|best| self.world.score > best.score) {
+                self.run.label = format!("Daily {}, score {}", daily.date, self.world.score);
+                self.run.score = self.world.score;
+                self.run.save(&name);
+            }
+        }
+
+        // A finished time attack run is kept if it is the best time of its course, along with its ghost
+        if let Some(seed) = self.time_attack.filter(|_| self.world.is_won()) {
+            let splits = self.world.leader().map_or(&[][..], |leader| &leader.splits);
+            self.new_record = self.records.record(seed, splits);
+            if self.new_record {
+                self.records.save();
+                self.run.label = format!(
+                    "Time attack #{}, {}",
+                    seed,
+                    time_attack::format_time(self.world.tick)
+                );
+                self.run.save(&Ghost::time_attack_name(seed));
+            }
+        }
+    }
+
+    /// The start_daily function, this is used to start the daily challenge,
+    /// the endless game with asteroids and a pad derived from today's date
+    fn start_daily(&mut self) {
+        self.level = Level {
+            name: "Daily".to_string(),
+            ..Level::default()
+        };
+        self.time_attack = None;
+        self.daily = Some(DailyRun::new(&self.daily_results));
+        self.restart();
+    }
+
+    /// The start_time_attack function, this is used to start a time attack on
+    /// the course generated from the given seed
+    fn start_time_attack(&mut self, seed: u64) {
+        self.level = time_attack::course(seed);
+        self.time_attack = Some(seed);
+        self.daily = None;
+        self.restart();
+    }
+
+    /// The statistics_screen function, this is used to show the statistics
+    /// while the Statistics scene runs
+    fn statistics_screen(&mut self, ctx: &mut BTerm) {
+        ctx.cls();
+        let catalog = &self.catalog;
+        ctx.print_color_centered(10, self.theme.title, BLACK, catalog.text("statistics.title"));
+
+        let statistics = &self.statistics;
+        let minutes = (statistics.flight_time / 60.0) as u64;
+        let mut lines = vec![
+            catalog.format("statistics.games_played", &[("count", &statistics.games_played)]),
+            catalog.format(
+                "statistics.flight_time",
+                &[
+                    ("hours", &(minutes / 60)),
+                    ("minutes", &format!("{:02}", minutes % 60)),
+                    ("seconds", &format!("{:02}", statistics.flight_time as u64 % 60)),
+                ],
+            ),
+            catalog.format("statistics.avoided", &[("count", &statistics.asteroids_avoided)]),
+            catalog.format("statistics.landings", &[("count", &statistics.landings)]),
+            catalog.format("statistics.fuel", &[("count", &statistics.fuel_burned)]),
+            catalog.format("statistics.deaths", &[("count", &statistics.deaths.values().sum::<u64>())]),
+        ];
+        lines.extend(statistics.deaths.iter().map(|(cause, count)| {
+            let cause = catalog.text(&format!("cause.{:?}", cause));
+            format!("  {}", catalog.format("statistics.death", &[("cause", &cause), ("count", count)]))
+        }));
+        lines.push(String::new());
+        lines.push(catalog.text("statistics.best_scores"));
+        if statistics.best_scores.is_empty() {
+            lines.push(format!("  {}", catalog.text("statistics.none")));
+        }
+        lines.extend(
+            statistics
+                .best_scores
+                .iter()
+                .map(|(mode, score)| format!("  {}: {}", mode, score)),
+        );
+
+        for (index, line) in lines.iter().enumerate() {
+            ctx.print(25, 13 + index as i32, line);
+        }
+        ctx.print_centered(15 + lines.len() as i32, self.hint("hint.back", Action::Back));
+        if self.pressed(Action::Back) {
+            self.change(Transition::Pop);
+        }
+    }
+
+    /// The achievements_screen function, this is used to list the achievements
+    /// while the Achievements scene runs, the unlocked ones are
+    /// shown in green with their description, the locked ones in gray
+    fn achievements_screen(&mut self, ctx: &mut BTerm) {
+        ctx.cls();
+        let catalog = &self.catalog;
+        ctx.print_color_centered(10, self.theme.title, BLACK, catalog.text("achievements.title"));
+        for (index, achievement) in ACHIEVEMENTS.iter().enumerate() {
+            // The achievements module names the achievements in English, the catalogs can translate them
+            let name = catalog
+                .lookup(&format!("achievement.{}.name", achievement.id))
+                .unwrap_or(achievement.name);
+            let description = catalog
+                .lookup(&format!("achievement.{}.description", achievement.id))
+                .unwrap_or(achievement.description);
+            let (color, status) = if self.achievements.is_unlocked(achievement.id) {
+                (self.theme.positive, "[x]")
+            } else {
+                (self.theme.disabled, "[ ]")
+            };
+            ctx.print_color(
+                15,
+                13 + index as i32 * 2,
+                color,
+                BLACK,
+                format!("{} {}: {}", status, name, description),
+            );
+        }
+        ctx.print_centered(
+            15 + ACHIEVEMENTS.len() as i32 * 2,
+            catalog.format(
+                "achievements.unlocked",
+                &[
+                    ("unlocked", &self.achievements.unlocked.len()),
+                    ("total", &ACHIEVEMENTS.len()),
+                    ("key", &catalog.key(self.bindings.key(Action::Back))),
+                ],
+            ),
+        );
+        if self.pressed(Action::Back) {
+            self.change(Transition::Pop);
+        }
+    }
+
+    /// The options_screen function, this is used to choose the theme, the
+    /// reduced motion and the language while the Options scene runs, a
+    /// sample of the arena is drawn in the chosen theme's colors,
+    /// and the options are saved as soon as they change
+    fn options_screen(&mut self, ctx: &mut BTerm) {
+        ctx.cls();
+        let theme = &self.theme;
+        let catalog = &self.catalog;
+        let bindings = &self.bindings;
+        ctx.print_color_centered(10, theme.title, BLACK, catalog.text("options.title"));
+        let arrows = format!(
+            "{}/{}",
+            catalog.key(bindings.key(Action::Left)),
+            catalog.key(bindings.key(Action::Right))
+        );
+        ctx.print(
+            20,
+            13,
+            catalog.format("options.theme", &[("key", &arrows), ("theme", &theme.name)]),
+        );
+        let state = catalog.text(if self.options.reduced_motion { "options.on" } else { "options.off" });
+        ctx.print(
+            20,
+            14,
+            catalog.format(
+                "options.reduced_motion",
+                &[("key", &catalog.key(bindings.key(Action::ReducedMotion))), ("state", &state)],
+            ),
+        );
+        ctx.print(
+            20,
+            15,
+            catalog.format(
+                "options.language",
+                &[
+                    ("key", &catalog.key(bindings.key(Action::Language))),
+                    ("language", &catalog.language.name()),
+                ],
+            ),
+        );
+
+        let samples = [
+            ("^", theme.ship, "options.ship"),
+            ("@", theme.asteroid, "options.asteroid"),
+            ("=====", theme.pad, "options.pad"),
+            ("=====", theme.pad_lit, "options.pad_lit"),
+            ("#####", theme.terrain, "options.ground"),
+            ("S T W", theme.pickup_shield, "options.power_ups"),
+        ];
+        for (index, (glyphs, color, label)) in samples.iter().enumerate() {
+            let y = 18 + index as i32;
+            ctx.print_color(24, y, *color, BLACK, glyphs);
+            ctx.print(32, y, catalog.text(label));
+        }
+        ctx.print_color(26, 23, theme.pickup_slow_time, BLACK, "T");
+        ctx.print_color(28, 23, theme.pickup_shockwave, BLACK, "W");
+        ctx.print_centered(26, self.hint("hint.back", Action::Back));
+
+        for key in self.keys.clone() {
+            if self.bindings.is(key, Action::Left) || self.bindings.is(key, Action::Right) {
+                self.theme = self.options.cycle_theme(self.bindings.is(key, Action::Left));
+                self.options.save();
+            } else if self.bindings.is(key, Action::ReducedMotion) {
+                self.options.reduced_motion = !self.options.reduced_motion;
+                self.theme.reduced_motion = self.options.reduced_motion;
+                self.options.save();
+            } else if self.bindings.is(key, Action::Language) {
+                self.options.language = self.options.language.next();
+                self.catalog = Catalog::new(self.options.language);
+                self.options.save();
+            } else if self.bindings.is(key, Action::Back) {
+                self.change(Transition::Pop);
+            }
+        }
+    }
+
+    /// The edit function, this is used to run the level editor while the
+    /// Editor scene runs, from there the player can test play the
+    /// level or go back to the main menu
+    fn edit(&mut self, ctx: &mut BTerm) {
+        let Some(editor) = self.editor.as_mut() else {
+            self.change(Transition::Pop);
+            return;
+        };
+        self.testing = false;
+
+        match editor.update(ctx, &self.keys, &self.bindings) {
+            Some(EditorAction::TestPlay) => {
+                self.level = editor.level.clone();
+                self.testing = true;
+                self.restart();
+            }
+            Some(EditorAction::Exit) => self.change(Transition::Pop),
+            None => editor.render(ctx, &self.theme, &self.catalog, &self.bindings),
+        }
+    }
+
+    /// The saveable function, this is used to know whether the game played
+    /// can be saved to be continued later: a recorded game of the endless
+    /// game or of a level that is not over yet. The time attack and the
+    /// daily challenge are raced in one go, they are never saved
+    fn saveable(&self) -> bool {
+        self.recording
+            && self.client.is_none()
+            && self.time_attack.is_none()
+            && self.daily.is_none()
+            && !self.world.is_over()
+            && !self.world.is_won()
+    }
+
+    /// The save_run function, this is used to save the game played in the
+    /// data directory if it can be, it becomes the saved run and replaces
+    /// the previous one
+    fn save_run(&mut self) {
+        if !self.saveable() {
+            return;
+        }
+        SavedRun::new(&self.level, &mut self.world).save();
+        self.saved_run = true;
+        self.continued = true;
+    }
+
+    /// The continue_run function, this is used to start the game of the saved
+    /// run again where it was left, an error is shown on the console if it
+    /// can't be read anymore
+    fn continue_run(&mut self) {
+        let Some(saved) = SavedRun::load() else {
+            eprintln!("There is no saved run to continue");
+            self.saved_run = false;
+            return;
+        };
+        self.level = saved.level.clone();
+        self.time_attack = None;
+        self.daily = None;
+        self.restart();
+        self.world = saved.world();
+        self.continued = true;
+    }
+
+    /// The open_editor function, this is used to open the level editor on the
+    /// given level file, an error is shown on the console if it can't be read
+    fn open_editor(&mut self, path: &Path) {
+        match Editor::open(path) {
+            Ok(editor) => {
+                self.editor = Some(editor);
+                self.change(Transition::Push(Scene::Editor));
+            }
+            Err(error) => eprintln!("{}", error),
+        }
     }
 
     /// The restart function, this is used to restart the game, ounce the player looses
-    /// It resets the state struct to its initial state except for the game_state that is kept
-    /// to Play
+    /// It resets the state struct to its initial state except for the scenes, the
+    /// game is played on top of the menu or the editor it was started from, or
+    /// replaces the results of the previous game
     fn restart(&mut self) {
-        self.character = character::Character::new((SCREEN_WIDTH / 2) as f32, SCREEN_HEIGHT as f32);
-        let mut random = RandomNumberGenerator::new();
-        self.asteroids = Vec::new();
-        let starting_point = random.range(0.0, SCREEN_WIDTH as f32);
-        self.asteroids
-            .push(hazards::Asteroid::new(starting_point, 0.0));
+        // A game restarted from the pause menu is over, it is counted before the new one starts
+        self.end_game();
+        self.continued = false;
+
+        // The daily challenge starts a new run every time, only the first one of the day is ranked
+        let random = match self.daily.as_mut() {
+            Some(daily) => {
+                *daily = DailyRun::new(&self.daily_results);
+                RandomNumberGenerator::seeded(daily.seed)
+            }
+            None => RandomNumberGenerator::new(),
+        };
+        self.world = World::from_level(&self.level, 1, random);
+        self.message = None;
+
+        // The time attack and the daily challenge are raced against the imported ghost, or the personal best
+        self.ghost = match (self.time_attack, self.daily.as_ref()) {
+            (None, None) => None,
+            _ if self.imported_ghost.is_some() => self.imported_ghost.clone(),
+            (Some(seed), _) => Ghost::load(&Ghost::time_attack_name(seed)),
+            (None, Some(daily)) => Ghost::load(&Ghost::daily_name(&daily.date)),
+        };
+        self.run = Ghost::default();
         self.frame_time = 0.0;
-        self.avoided = 0;
-        self.landing_pad = map::LandingPad::new(random.range(0, SCREEN_WIDTH));
-        self.score = 0;
-        self.mode = Gamemode::Play;
+        self.achievements.start_run();
+        self.events.start_game();
+        self.new_record = false;
+        self.recording = !self.testing && self.control.is_none();
+        self.toast = None;
+        self.popups.clear();
+        self.change(match self.scene() {
+            Scene::Lost | Scene::Won => Transition::Switch(Scene::Play),
+            Scene::Pause => Transition::PopTo(Scene::Play),
+            _ => Transition::Push(Scene::Play),
+        });
+    }
+
+    /// The poll_events function, this is used to empty bracket-lib's event
+    /// queue at the start of every frame: the keys pressed are kept for the
+    /// scene to handle, the game is paused when the window loses the focus
+    /// and closing the window quits the game the same way the Quit key does,
+    /// so that the game left is saved
+    fn poll_events(&mut self) {
+        self.keys.clear();
+        let mut input = INPUT.lock();
+        while let Some(event) = input.pop() {
+            match event {
+                BEvent::KeyboardInput { key, pressed: true, .. } => self.keys.push(key),
+                BEvent::Focused { focused: false } if self.pausable() => {
+                    self.change(Transition::Push(Scene::Pause));
+                }
+                BEvent::CloseRequested => self.change(Transition::Quit),
+                _ => {}
+            }
+        }
+    }
+
+    /// The pausable function, this is used to know whether the game can be
+    /// paused on its own: a local game flown with the keyboard, with no other
+    /// change of the scenes asked for, such as quitting
+    fn pausable(&self) -> bool {
+        self.scene() == Scene::Play
+            && self.client.is_none()
+            && self.control.is_none()
+            && self.transition.is_none()
+    }
+
+    /// The scene function, this is used to get the scene on top of the stack,
+    /// the one being run
+    fn scene(&self) -> Scene {
+        self.scenes.last().copied().unwrap_or(Scene::Menu)
+    }
+
+    /// The change function, this is used to ask for a change of the scenes,
+    /// it replaces the change asked for earlier in the same frame if any
+    fn change(&mut self, transition: Transition) {
+        self.transition = Some(transition);
+    }
+
+    /// The apply_transition function, this is used to make the change of the
+    /// scenes asked for during the last frame, calling the exit hook of the
+    /// scenes left and the enter hook of the scenes entered. The scenes played
+    /// fade in, the overlays show up at once over the frozen scene
+    fn apply_transition(&mut self, ctx: &mut BTerm) {
+        let Some(transition) = self.transition.take() else {
+            return;
+        };
+        let overlay = match transition {
+            Transition::Push(scene) => {
+                self.scenes.push(scene);
+                self.enter(scene);
+                scene.is_overlay()
+            }
+            // The main menu at the bottom is never popped
+            Transition::Pop if self.scenes.len() > 1 => {
+                let scene = self.scene();
+                self.scenes.pop();
+                self.exit(scene);
+                scene.is_overlay()
+            }
+            Transition::Pop => return,
+            Transition::Switch(scene) => {
+                if let Some(left) = self.scenes.pop() {
+                    self.exit(left);
+                }
+                self.scenes.push(scene);
+                self.enter(scene);
+                false
+            }
+            Transition::PopTo(scene) => {
+                let mut overlay = true;
+                while self.scenes.len() > 1 && self.scene() != scene {
+                    let left = self.scene();
+                    self.scenes.pop();
+                    self.exit(left);
+                    overlay &= left.is_overlay();
+                }
+                overlay
+            }
+            Transition::Quit => {
+                while let Some(scene) = self.scenes.pop() {
+                    self.exit(scene);
+                }
+                ctx.cls();
+                ctx.quitting = true;
+                return;
+            }
+        };
+        if !overlay && !self.theme.reduced_motion {
+            self.fade = FADE_DURATION;
+        }
+    }
+
+    /// The enter function, this is the hook called when a scene is pushed or
+    /// switched to, the menus of the scenes are shown from their first item
+    fn enter(&mut self, scene: Scene) {
+        match scene {
+            Scene::Pause => {
+                self.pause_menu.reset();
+                self.save_run();
+            }
+            Scene::Confirm(_) => self.confirm_menu.reset(),
+            Scene::Lost | Scene::Won => self.results_menu.reset(),
+            _ => {}
+        }
+    }
+
+    /// The exit function, this is the hook called when a scene is left for
+    /// good: the game played is counted in the statistics and the records,
+    /// unless it is left before its end and saved to be continued, and the
+    /// level edited is put away, ending its test plays
+    fn exit(&mut self, scene: Scene) {
+        match scene {
+            Scene::Play if self.saveable() => {
+                self.save_run();
+                self.recording = false;
+                self.statistics.save();
+            }
+            Scene::Play => {
+                // The server is told at once that the player left, rather than waiting for them to time out
+                if let Some(client) = self.client.as_ref() {
+                    client.leave();
+                }
+                self.end_game();
+            }
+            Scene::Editor => {
+                self.level = Level::default();
+                self.testing = false;
+            }
+            _ => {}
+        }
+    }
+
+    /// The run function, this is used to run the given scene for a frame: to
+    /// handle its input and to draw it
+    fn run(&mut self, scene: Scene, ctx: &mut BTerm) {
+        match scene {
+            Scene::Menu => self.main_menu(ctx),
+            Scene::Play => self.play(ctx),
+            Scene::Pause => self.pause(ctx),
+            Scene::Confirm(confirmation) => self.confirm(confirmation, ctx),
+            Scene::Controls => self.controls_screen(ctx),
+            Scene::Lost => self.lost(ctx),
+            Scene::Won => self.won(ctx),
+            Scene::Editor => self.edit(ctx),
+            Scene::Achievements => self.achievements_screen(ctx),
+            Scene::Statistics => self.statistics_screen(ctx),
+            Scene::Options => self.options_screen(ctx),
+        }
     }
 
-    /// The keep_in_bounds function, this is used to keep the character's ship
-    /// inside the window generated by Btermbuilder.
-    fn keep_in_bounds(coordinate: f32, min: i32, max: i32) -> f32 {
-        if coordinate > max as f32 {
-            return max as f32;
+    /// The draw function, this is used to draw the given scene frozen under an
+    /// overlay, without running it, the game being played is drawn dimmed
+    fn draw(&mut self, scene: Scene, ctx: &mut BTerm) {
+        match scene {
+            Scene::Play if self.client.is_none() => self.render_frozen(ctx),
+            Scene::Pause => self.render_pause(ctx),
+            _ => {}
         }
+    }
 
-        if coordinate < min as f32 {
-            return min as f32;
+    /// The render_fade function, this is used to cover the screen with black
+    /// on the fancy console, less and less as the fade goes
+    fn render_fade(&mut self, ctx: &mut BTerm) {
+        if self.fade <= 0.0 {
+            return;
         }
-        coordinate
+        let alpha = (self.fade / FADE_DURATION * 255.0) as u8;
+        ctx.set_active_console(1);
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                ctx.set_fancy(
+                    PointF::new(x as f32, y as f32),
+                    1,
+                    Degrees::new(0.0),
+                    PointF::new(1.0, 1.0),
+                    RGBA::from_u8(0, 0, 0, alpha),
+                    RGBA::from_u8(0, 0, 0, 0),
+                    to_cp437('█'),
+                );
+            }
+        }
+        ctx.set_active_console(0);
+        self.fade -= ctx.frame_time_ms;
     }
 }
 
 /// The implementation of the GameState trait for the State struct. It is imperative for
 /// the state struct to implement the GameState trait in order to be used in the main loop.
-/// This specific implementation checks for a particular variant of the gamemode enum and launches
-/// the appropriate state's method.
+/// This specific implementation reads the events of the window, makes the change of scenes asked for, draws the
+/// scenes under the overlays frozen and runs the scene on top of the stack.
 impl GameState for State {
     fn tick(&mut self, ctx: &mut BTerm) {
-        match self.mode {
-            Gamemode::Menu => self.main_menu(ctx),
-            Gamemode::Play => self.play(ctx),
-            Gamemode::Pause => self.pause(ctx),
-            Gamemode::Quit => self.quit(ctx),
-            Gamemode::Lost => self.lost(ctx),
+        self.poll_events();
+        self.apply_transition(ctx);
+        if ctx.quitting {
+            return;
         }
+
+        // Every scene starts from an empty fancy console, so that nothing is left over from the previous scene
+        ctx.set_active_console(1);
+        ctx.cls();
+        ctx.set_active_console(0);
+
+        // The overlays are drawn over the scenes under them, down to the first one that is not an overlay
+        let top = self.scene();
+        let base = self.scenes.iter().rposition(|scene| !scene.is_overlay()).unwrap_or(0);
+        for index in base..self.scenes.len() - 1 {
+            self.draw(self.scenes[index], ctx);
+        }
+        self.run(top, ctx);
+        self.render_fade(ctx);
     }
 }
 
@@ -341,8 +1479,72 @@ impl GameState for State {
 /// - .with_fps_cap : to set the game's frame rate to 60.0
 /// - .with_fullscreen : to set the game to fullscreen
 ///
-/// It then launches the main loop with the State::new() as the game's state
+/// It then launches the main loop with the State::new() as the game's state, the
+/// following arguments are accepted :
+///
+/// - `--connect <address>` : starts the game directly online, connected to a server
+///   started with the rover_server binary
+/// - `--control stdio` : starts the game directly, with the ship flown by another
+///   program through JSON lines on stdin and stdout, see the control module
+/// - `--level <path>` : plays the given level file instead of the endless game
+/// - `--time-attack <seed>` : starts the time attack course generated from the
+///   given seed, to race a friend on the same course
+/// - `--ghost <path>` : races the time attack and the daily challenge against
+///   the given ghost file instead of the personal bests
+/// - `--edit <path>` : opens the level editor on the given level file, which is
+///   created when saving if it does not exist yet
+/// - `--events <path>` : appends the events of the games played to the given
+///   file, as JSON lines, see the telemetry module
 fn main() -> BError {
+    let mut state = State::new();
+    let mut args = std::env::args().skip(1);
+    let mut play = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--connect" => {
+                let address = args
+                    .next()
+                    .unwrap_or_else(|| net::DEFAULT_ADDRESS.to_string());
+                state.client = Some(net::Client::connect(&address)?);
+                play = true;
+            }
+            "--control" => match args.next().as_deref() {
+                Some("stdio") => {
+                    state.control = Some(StdioControl::new());
+                    play = true;
+                }
+                other => return Err(format!("Unknown control mode {:?}, expected stdio", other).into()),
+            },
+            "--level" => {
+                let path = args.next().ok_or("--level expects the path of a level file")?;
+                state.level = Level::load(Path::new(&path))?;
+            }
+            "--time-attack" => {
+                let seed = args
+                    .next()
+                    .and_then(|seed| seed.parse().ok())
+                    .ok_or("--time-attack expects the number of a course")?;
+                state.time_attack = Some(seed);
+                state.level = time_attack::course(seed);
+                play = true;
+            }
+            "--ghost" => {
+                let path = args.next().ok_or("--ghost expects the path of a ghost file")?;
+                state.imported_ghost = Some(Ghost::import(Path::new(&path))?);
+            }
+            "--events" => {
+                let path = args.next().ok_or("--events expects the path of the event log")?;
+                state.events.subscribe(JsonLinesSink::create(Path::new(&path))?);
+            }
+            "--edit" => {
+                let path = args.next().ok_or("--edit expects the path of a level file")?;
+                state.editor = Some(Editor::open(Path::new(&path))?);
+                state.change(Transition::Push(Scene::Editor));
+            }
+            _ => eprintln!("Ignoring unknown argument {}", arg),
+        }
+    }
+
     let context = BTermBuilder::simple80x50()
         .with_fancy_console(80, 50, "terminal8x8.png")
         .with_title("Rust Rover")
@@ -352,5 +1554,10 @@ fn main() -> BError {
         .with_fullscreen(true)
         .build()?;
 
-    main_loop(context, State::new())
+    // The arguments can start the game directly, in which case the world is built from the level given
+    if play {
+        state.restart();
+    }
+
+    main_loop(context, state)
 }
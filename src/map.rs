@@ -1,46 +1,159 @@
 use crate::character::Character;
-use crate::SCREEN_HEIGHT;
+use crate::theme::Theme;
 use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The largest difference between the horizontal speed of a ship and the one of a moving pad for the ship to touch
+/// down on it, a faster ship skids on the pad instead of landing.
+pub const LANDING_TOLERANCE: f32 = 0.15;
+
+/// The PadMotion enum lists how a landing pad moves. Moving pads swing back and forth around where they were placed,
+/// over the given period in ticks.
+///
+/// # Variants
+/// - Static: The pad doesn't move
+/// - Patrol: The pad patrols from side to side, up to the given distance from where it was placed
+/// - Elevator: The pad rises and sinks like an elevator, up to the given distance from its height
+/// - Shrinking: The pad narrows down to the given width, then widens back
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PadMotion {
+    #[default]
+    Static,
+    Patrol { distance: i32, period: u64 },
+    Elevator { distance: i32, period: u64 },
+    Shrinking { min_width: i32, period: u64 },
+}
+
+impl PadMotion {
+    /// The wave function returns where a pad moving over the given period is at the given tick, between -1 and 1, and
+    /// how much that changes per tick. It starts at 0 and rises first, at a constant speed like a patrolling guard.
+    fn wave(tick: u64, period: u64) -> (f32, f32) {
+        let period = period.max(1);
+        let phase = ((tick % period) as f32 / period as f32 + 0.25).fract();
+        let rate = 4.0 / period as f32;
+        if phase < 0.5 {
+            (4.0 * phase - 1.0, rate)
+        } else {
+            (3.0 - 4.0 * phase, -rate)
+        }
+    }
+}
 
 /// The LandingPad struct is used to create and manage the landing pads that the character will be trying to land on.
 /// The landing pads are the goal of the game, and the character must land on them to win.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LandingPad {
-    x: i32,
-    y: i32,
+    pub x: i32,
+    pub y: i32,
     pub heigth: i32,
-    pad_width: i32,
+    pub pad_width: i32,
+    /// The motion field tells how the pad moves, the home fields are where it was placed and moves around.
+    pub motion: PadMotion,
+    pub home_x: i32,
+    pub home_heigth: i32,
+    pub home_width: i32,
+    /// The velocity field is the horizontal speed of the pad during the current tick.
+    pub velocity: f32,
 }
 
 impl LandingPad {
     /// The new function is used to create a new instance of the LandingPad struct.
-    /// It takes in an x coordinate and returns a new instance of the LandingPad struct.
-    /// The height and width of the landing pad are randomly generated.
-    pub fn new(x: i32) -> Self {
-        let mut random = RandomNumberGenerator::new();
-        let height = random.range(SCREEN_HEIGHT / 4, SCREEN_HEIGHT / 2);
+    /// It takes in an x coordinate and the y coordinate of the bottom of the arena, and returns a new instance of the LandingPad struct.
+    /// The height and width of the landing pad are randomly generated with the given random number generator.
+    pub fn new(x: i32, y: i32, random: &mut RandomNumberGenerator) -> Self {
+        let height = random.range(y / 4, y / 2);
         let mut pad_width = random.range(3, 11);
         if pad_width % 2 == 0 {
             pad_width += 1;
         }
+        LandingPad::placed(x, y, height, pad_width, PadMotion::Static)
+    }
+
+    /// The placed function is used to create a landing pad at the given place, which then moves with the given motion.
+    pub fn placed(x: i32, y: i32, heigth: i32, pad_width: i32, motion: PadMotion) -> Self {
         LandingPad {
             x,
-            y: SCREEN_HEIGHT,
-            heigth: height,
+            y,
+            heigth,
             pad_width,
+            motion,
+            home_x: x,
+            home_heigth: heigth,
+            home_width: pad_width,
+            velocity: 0.0,
+        }
+    }
+
+    /// The is_moving function returns true if the pad has a motion.
+    pub fn is_moving(&self) -> bool {
+        self.motion != PadMotion::Static
+    }
+
+    /// The update function moves the pad to where its motion puts it at the given tick. A shrinking pad keeps an odd
+    /// width, so that it stays centered.
+    pub fn update(&mut self, tick: u64) {
+        self.velocity = 0.0;
+        match self.motion {
+            PadMotion::Static => {}
+            PadMotion::Patrol { distance, period } => {
+                let (offset, rate) = PadMotion::wave(tick, period);
+                self.x = self.home_x + (offset * distance as f32).round() as i32;
+                self.velocity = rate * distance as f32;
+            }
+            PadMotion::Elevator { distance, period } => {
+                let (offset, _) = PadMotion::wave(tick, period);
+                self.heigth = self.home_heigth + (offset * distance as f32).round() as i32;
+            }
+            PadMotion::Shrinking { min_width, period } => {
+                let (offset, _) = PadMotion::wave(tick, period);
+                let shrink = ((self.home_width - min_width) as f32 * offset.abs()).round() as i32;
+                let width = self.home_width - shrink;
+                self.pad_width = if width % 2 == 0 { width + 1 } else { width }.min(self.home_width);
+            }
         }
     }
 
+    /// The covers function returns true if the given position is on the pad's surface or inside its pillar.
+    pub fn covers(&self, x: f32, y: f32) -> bool {
+        let half = (self.pad_width / 2) as f32 + 0.5;
+        (x - self.x as f32).abs() <= half && y >= (self.y - self.heigth) as f32
+    }
+
+    /// The chip function breaks a piece off the pad, hit by an asteroid: a pad wider than a column loses a column on
+    /// each side, a single column gets shorter, down to a stump of height 1. The place a moving pad moves around is
+    /// chipped too, so that its motion doesn't grow it back.
+    pub fn chip(&mut self) {
+        if self.home_width > 1 {
+            self.home_width = (self.home_width - 2).max(1);
+            self.pad_width = self.pad_width.min(self.home_width);
+        } else {
+            self.home_heigth = (self.home_heigth - 1).max(1);
+            self.heigth = (self.heigth - 1).max(1);
+        }
+    }
+
+    /// The accepts function is used to check if the character moves slowly enough, relative to the pad, to touch down
+    /// on it. Every ship touches down on a pad which doesn't move.
+    pub fn accepts(&self, character: &Character) -> bool {
+        !self.is_moving() || (character.momentum.horizontal - self.velocity).abs() <= LANDING_TOLERANCE
+    }
+
     /// The render function is used to draw the landing pad to the screen. Since it uses floating-point fancy-rendering, it must
     /// first shift on the fancy-console (id 1), before drawing the landing pad, and then shift back to the main console (id 0).
-    pub fn render(&mut self, ctx: &mut BTerm) {
+    pub fn render(&mut self, ctx: &mut BTerm, theme: &Theme) {
+        self.render_color(ctx, theme.pad);
+    }
+
+    /// The render_color function draws the landing pad like the render function, but in the given color.
+    pub fn render_color(&mut self, ctx: &mut BTerm, color: (u8, u8, u8)) {
         for i in 0..self.heigth {
-            ctx.set(self.x, self.y - i, GREEN, BLACK, to_cp437('H'));
+            ctx.set(self.x, self.y - i, color, BLACK, to_cp437('H'));
         }
         for i in 0..self.pad_width {
             ctx.set(
                 self.x - i + (self.pad_width / 2),
                 self.y - self.heigth,
-                GREEN,
+                color,
                 BLACK,
                 to_cp437('='),
             );
@@ -60,3 +173,142 @@ impl LandingPad {
         false
     }
 }
+
+/// The Terrain struct is used to store the ground of the map as a heightmap, with one height per column of the screen.
+/// An empty heightmap is a flat ground at the bottom of the screen.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Terrain {
+    pub heights: Vec<i32>,
+}
+
+impl Terrain {
+    /// The height_at function returns the height of the ground at the given column, columns outside of the
+    /// heightmap are at the bottom of the screen.
+    pub fn height_at(&self, x: i32) -> i32 {
+        if x < 0 {
+            return 0;
+        }
+        self.heights.get(x as usize).copied().unwrap_or(0)
+    }
+
+    /// The carve function digs a crater in the ground where an asteroid hit it, deepest at the given column and
+    /// shallower towards its radius. The debris are thrown on both sides of the crater, raising the ground there, but
+    /// never higher than the given height. A flat ground gets a heightmap as wide as the given width.
+    pub fn carve(&mut self, x: i32, radius: i32, depth: i32, width: i32, max_height: i32) {
+        if self.heights.len() != width as usize {
+            self.heights.resize(width as usize, 0);
+        }
+        for offset in -radius..=radius {
+            let dig = depth - offset.abs() * depth / (radius + 1);
+            if let Some(height) = self.heights.get_mut((x + offset) as usize) {
+                *height = (*height - dig).max(0);
+            }
+        }
+        for rim in [x - radius - 1, x + radius + 1] {
+            if let Some(height) = self.heights.get_mut(rim as usize) {
+                *height = (*height + 1).min(max_height);
+            }
+        }
+    }
+
+    /// The render function is used to draw the ground, filling every column from the bottom of the screen (y_bound)
+    /// up to its height, in the given theme's color.
+    pub fn render(&self, ctx: &mut BTerm, y_bound: i32, theme: &Theme) {
+        for (x, height) in self.heights.iter().enumerate() {
+            for i in 0..*height {
+                ctx.set(x as i32, y_bound - i, theme.terrain, BLACK, to_cp437('#'));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wave_starts_at_zero_and_rises_first() {
+        let (offset, rate) = PadMotion::wave(0, 100);
+        assert!(offset.abs() < 1e-6);
+        assert!(rate > 0.0);
+        assert!((PadMotion::wave(25, 100).0 - 1.0).abs() < 1e-6);
+        assert!(PadMotion::wave(50, 100).0.abs() < 1e-6);
+        assert!((PadMotion::wave(75, 100).0 + 1.0).abs() < 1e-6);
+        assert!(PadMotion::wave(60, 100).1 < 0.0);
+    }
+
+    #[test]
+    fn wave_stays_in_range_and_matches_its_rate() {
+        let period = 160;
+        for tick in 0..2 * period {
+            let (offset, rate) = PadMotion::wave(tick, period);
+            assert!((-1.0..=1.0).contains(&offset));
+            // Away from the turns, the offset changes by the rate every tick
+            let (next, _) = PadMotion::wave(tick + 1, period);
+            if (next - offset - rate).abs() > 1e-4 {
+                assert!(offset.abs() > 0.95 || next.abs() > 0.95, "tick {}", tick);
+            }
+        }
+        assert_eq!(PadMotion::wave(3, period), PadMotion::wave(3 + period, period));
+    }
+
+    #[test]
+    fn wave_survives_a_zero_period() {
+        let (offset, rate) = PadMotion::wave(7, 0);
+        assert!(offset.is_finite() && rate.is_finite());
+    }
+
+    #[test]
+    fn patrolling_pads_move_around_their_home() {
+        let mut pad = LandingPad::placed(40, 50, 10, 5, PadMotion::Patrol { distance: 8, period: 100 });
+        pad.update(25);
+        assert_eq!(pad.x, 48);
+        assert!(pad.velocity < 1e-6);
+        pad.update(75);
+        assert_eq!(pad.x, 32);
+        pad.update(0);
+        assert_eq!(pad.x, 40);
+        assert!(pad.velocity > 0.0);
+    }
+
+    #[test]
+    fn shrinking_pads_keep_an_odd_width() {
+        let mut pad = LandingPad::placed(40, 50, 10, 7, PadMotion::Shrinking { min_width: 2, period: 100 });
+        for tick in 0..100 {
+            pad.update(tick);
+            assert_eq!(pad.pad_width % 2, 1);
+            assert!((1..=7).contains(&pad.pad_width));
+        }
+    }
+
+    #[test]
+    fn carve_digs_a_crater_with_rims() {
+        let mut terrain = Terrain {
+            heights: vec![5; 20],
+        };
+        terrain.carve(10, 2, 3, 20, 6);
+        assert_eq!(terrain.heights[10], 2);
+        assert_eq!(terrain.heights[9], 3);
+        assert_eq!(terrain.heights[11], 3);
+        assert_eq!(terrain.heights[8], 4);
+        assert_eq!(terrain.heights[12], 4);
+        assert_eq!(terrain.heights[7], 6);
+        assert_eq!(terrain.heights[13], 6);
+        assert_eq!(terrain.heights[0], 5);
+
+        // The rims never rise above the given height
+        terrain.carve(10, 2, 3, 20, 6);
+        assert_eq!(terrain.heights[7], 6);
+    }
+
+    #[test]
+    fn carve_widens_a_flat_ground_and_stays_above_the_bottom() {
+        let mut terrain = Terrain::default();
+        terrain.carve(0, 1, 4, 30, 10);
+        assert_eq!(terrain.heights.len(), 30);
+        assert_eq!(terrain.heights[0], 0);
+        assert_eq!(terrain.heights[2], 1);
+        assert_eq!(terrain.height_at(-1), 0);
+        assert_eq!(terrain.height_at(30), 0);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::bindings::{Action, Bindings};
+use crate::theme::Theme;
+use crate::SCREEN_WIDTH;
+use bracket_lib::prelude::*;
+
+/// The MenuItem struct is an entry of a menu, it either triggers an action or opens a submenu. The key bound to the
+/// action triggers it directly, without moving the selection to it, even from another level of the menu.
+pub struct MenuItem {
+    /// The label field is the text of the item, usually the hint naming the key bound to its action.
+    pub label: String,
+    /// The action field is what the item does, it is None for the items opening a submenu.
+    pub action: Option<Action>,
+    /// The enabled field tells whether the item can be chosen, a disabled item is grayed out and skipped.
+    pub enabled: bool,
+    /// The submenu field lists the items of the submenu the item opens, it is empty for the items triggering an action.
+    pub submenu: Vec<MenuItem>,
+}
+
+impl MenuItem {
+    /// The new function returns an enabled item triggering the given action.
+    pub fn new(label: String, action: Action) -> Self {
+        MenuItem {
+            label,
+            action: Some(action),
+            enabled: true,
+            submenu: Vec::new(),
+        }
+    }
+
+    /// The submenu function returns an enabled item opening a submenu of the given items.
+    pub fn submenu(label: String, items: Vec<MenuItem>) -> Self {
+        MenuItem {
+            label,
+            action: None,
+            enabled: true,
+            submenu: items,
+        }
+    }
+
+    /// The enabled function returns the item enabled or disabled.
+    pub fn enabled(self, enabled: bool) -> Self {
+        MenuItem { enabled, ..self }
+    }
+}
+
+/// The Menu struct is the state of a menu shown on screen: the selected item and the open submenus. The items
+/// themselves are given every frame, so that their labels follow the language and their enabled state follows the
+/// game. The menu is navigated with the keys bound to MenuUp, MenuDown, Select and Back, or with the mouse.
+#[derive(Default)]
+pub struct Menu {
+    selected: usize,
+    /// The path field is the index of every open submenu's item, from the top level down.
+    path: Vec<usize>,
+    /// The mouse field is where the mouse was on the last frame, the mouse only selects an item when it moves so that
+    /// it doesn't fight the keyboard.
+    mouse: (i32, i32),
+}
+
+impl Menu {
+    /// The reset function closes the submenus and selects the first item, as when the menu is shown for the first
+    /// time.
+    pub fn reset(&mut self) {
+        self.selected = 0;
+        self.path.clear();
+    }
+
+    /// The update function handles the keys pressed during a frame, in order, and the mouse, the items being shown from
+    /// the top line down. It returns the first action chosen, if any: the one of the item clicked or selected with
+    /// Select, the one bound to a key pressed, or Back when Back is pressed outside of the submenus.
+    pub fn update(
+        &mut self,
+        ctx: &BTerm,
+        keys: &[VirtualKeyCode],
+        bindings: &Bindings,
+        items: &[MenuItem],
+        top: i32,
+    ) -> Option<Action> {
+        let shown = self.shown(items);
+        if shown.is_empty() {
+            return None;
+        }
+        if !shown.get(self.selected).is_some_and(|item| item.enabled) {
+            self.selected = next_enabled(shown, self.selected, 1).unwrap_or(0);
+        }
+
+        let mouse = ctx.mouse_pos();
+        let hovered = item_at(shown, top, mouse).filter(|index| shown[*index].enabled);
+        if mouse != self.mouse {
+            self.mouse = mouse;
+            if let Some(index) = hovered {
+                self.selected = index;
+            }
+        }
+        if ctx.left_click {
+            if let Some(index) = hovered {
+                return self.choose(items, index);
+            }
+        }
+
+        keys.iter().find_map(|key| self.press(items, bindings, *key))
+    }
+
+    /// The press function handles a single key pressed, and returns the action it chooses, if any.
+    fn press(&mut self, items: &[MenuItem], bindings: &Bindings, key: VirtualKeyCode) -> Option<Action> {
+        let shown = self.shown(items);
+        if bindings.is(key, Action::MenuUp) {
+            self.selected = next_enabled(shown, self.selected + shown.len() - 1, shown.len() - 1).unwrap_or(0);
+        } else if bindings.is(key, Action::MenuDown) {
+            self.selected = next_enabled(shown, self.selected + 1, 1).unwrap_or(0);
+        } else if bindings.is(key, Action::Select) {
+            return self.choose(items, self.selected);
+        } else if bindings.is(key, Action::Back) {
+            match self.path.pop() {
+                Some(index) => self.selected = index,
+                None => return Some(Action::Back),
+            }
+        } else {
+            return hotkey(items, bindings, key);
+        }
+        None
+    }
+
+    /// The render function draws the items from the top line down, centered, the selected one between arrows in the
+    /// highlight color of the given theme and the disabled ones in its disabled color. An open submenu is drawn under the
+    /// label of the item that opened it.
+    pub fn render(&self, ctx: &mut BTerm, items: &[MenuItem], top: i32, theme: &Theme) {
+        if let Some(parent) = self.parent(items) {
+            ctx.print_color_centered(top - 2, theme.title, BLACK, &parent.label);
+        }
+        for (index, item) in self.shown(items).iter().enumerate() {
+            let (x, y) = (label_x(item), top + index as i32);
+            let width = item.label.chars().count() as i32;
+            if !item.enabled {
+                ctx.print_color(x, y, theme.disabled, BLACK, &item.label);
+            } else if index == self.selected {
+                ctx.print_color(x - 2, y, theme.highlight, BLACK, ">");
+                ctx.print_color(x, y, theme.highlight, BLACK, &item.label);
+                ctx.print_color(x + width + 1, y, theme.highlight, BLACK, "<");
+            } else {
+                ctx.print_color(x, y, WHITE, BLACK, &item.label);
+            }
+        }
+    }
+
+    /// The shown function returns the items of the deepest open submenu, or the top level items if none is open.
+    fn shown<'a>(&self, items: &'a [MenuItem]) -> &'a [MenuItem] {
+        self.parent(items).map_or(items, |parent| &parent.submenu)
+    }
+
+    /// The parent function returns the item that opened the deepest open submenu, if one is open.
+    fn parent<'a>(&self, items: &'a [MenuItem]) -> Option<&'a MenuItem> {
+        let mut parent = None;
+        let mut shown = items;
+        for index in &self.path {
+            let item = shown.get(*index)?;
+            shown = &item.submenu;
+            parent = Some(item);
+        }
+        parent
+    }
+
+    /// The choose function chooses the item of the given index among the shown ones: it returns its action, or opens
+    /// its submenu.
+    fn choose(&mut self, items: &[MenuItem], index: usize) -> Option<Action> {
+        let item = self.shown(items).get(index).filter(|item| item.enabled)?;
+        if item.submenu.is_empty() {
+            return item.action;
+        }
+        self.path.push(index);
+        self.selected = next_enabled(&item.submenu, 0, 1).unwrap_or(0);
+        None
+    }
+}
+
+/// The next_enabled function returns the index of the first enabled item met going through the items from the given
+/// index by the given step, wrapping around, or None if every item is disabled.
+fn next_enabled(items: &[MenuItem], from: usize, step: usize) -> Option<usize> {
+    (0..items.len())
+        .map(|offset| (from + offset * step) % items.len())
+        .find(|index| items[*index].enabled)
+}
+
+/// The hotkey function returns the action of the enabled item, at any level of the menu, whose action is bound to the
+/// given key.
+fn hotkey(items: &[MenuItem], bindings: &Bindings, key: VirtualKeyCode) -> Option<Action> {
+    items.iter().filter(|item| item.enabled).find_map(|item| match item.action {
+        Some(action) if bindings.is(key, action) => Some(action),
+        _ => hotkey(&item.submenu, bindings, key),
+    })
+}
+
+/// The label_x function returns the column an item's label starts at, so that it is centered on the screen.
+fn label_x(item: &MenuItem) -> i32 {
+    (SCREEN_WIDTH - item.label.chars().count() as i32) / 2
+}
+
+/// The item_at function returns the index of the shown item under the mouse, arrows included.
+fn item_at(items: &[MenuItem], top: i32, (x, y): (i32, i32)) -> Option<usize> {
+    let index = usize::try_from(y - top).ok()?;
+    let item = items.get(index)?;
+    let start = label_x(item) - 2;
+    let end = label_x(item) + item.label.chars().count() as i32 + 2;
+    (x >= start && x < end).then_some(index)
+}
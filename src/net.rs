@@ -0,0 +1,688 @@
+use crate::character::{Character, Momentum, ShipInput};
+use crate::hazards::{Asteroid, Pickup, PickupKind};
+use crate::level::Level;
+use crate::weapon::Projectile;
+use crate::world::{Arena, World};
+use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The address used by the server and the clients when none is given on the command line.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+/// The duration of a server tick, it matches the game window's 60 fps cap so that asteroids fall at the same speed
+/// online and offline.
+pub const SERVER_TICK: Duration = Duration::from_micros(16_667);
+
+/// The time after which a silent client is dropped by the server.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The time a client waits for the server's welcome before sending its join request again.
+const JOIN_RETRY: Duration = Duration::from_secs(1);
+
+/// The time the server waits once every ship was destroyed before starting a new round.
+const ROUND_DELAY: Duration = Duration::from_secs(3);
+
+/// The size of the buffer used to receive datagrams, large enough for any UDP payload.
+const BUFFER_SIZE: usize = 65_536;
+
+/// The number of inputs the server keeps for a client that sends them faster than the ticks, the oldest ones are
+/// dropped beyond it so that the ship doesn't lag further and further behind.
+const MAX_QUEUED_INPUTS: usize = 8;
+
+/// The ClientMessage enum lists the datagrams a client sends to the server.
+#[derive(Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// Join asks the server for a ship, the server answers with a Welcome message.
+    Join,
+    /// Input carries one frame of thrust, the server flies the ship with one input per tick, in order, and echoes the
+    /// sequence number back in the snapshots once it was applied.
+    Input { sequence: u32, input: ShipInput },
+    /// Leave tells the server that the client quit and its ship can be removed.
+    Leave,
+}
+
+/// The ServerMessage enum lists the datagrams the server sends to its clients.
+#[derive(Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// Welcome gives a client the id of the ship it controls and the arena of the round being played, the snapshots
+    /// only carry the parts of the arena that change.
+    Welcome { player: u32, round: u32, arena: Box<Arena> },
+    /// Snapshot carries the authoritative state of the world, it is sent to every client after each tick.
+    Snapshot(Box<Snapshot>),
+}
+
+/// The Snapshot struct is a serializable copy of a World, as seen by the server at the end of a tick.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The round field counts the rounds played on the server, a client with the arena of another round asks for the
+    /// new one.
+    pub round: u32,
+    pub tick: u64,
+    pub score: usize,
+    pub ships: Vec<ShipState>,
+    pub asteroids: Vec<AsteroidState>,
+    pub pickups: Vec<PickupState>,
+    pub projectiles: Vec<Projectile>,
+    /// The slow_time field is the number of ticks time stays slowed down, see World::slow_time.
+    pub slow_time: u64,
+    /// The wind, terrain and pads fields are the parts of the arena that change during a round, see Arena.
+    pub wind: f32,
+    pub terrain: Vec<i32>,
+    pub pads: Vec<PadState>,
+}
+
+/// The PadState struct describes where a landing pad is in a snapshot, moved or chipped since the round started.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PadState {
+    pub x: i32,
+    pub heigth: i32,
+    pub width: i32,
+    pub velocity: f32,
+}
+
+/// The ShipState struct describes a single ship in a snapshot.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShipState {
+    /// The id of the player controlling the ship.
+    pub player: u32,
+    pub x: f32,
+    pub y: f32,
+    pub vertical: f32,
+    pub horizontal: f32,
+    pub destroyed: bool,
+    pub landed: bool,
+    /// The number of ticks the ship's shield still lasts.
+    pub shield: u64,
+    pub cooldown: u32,
+    /// The target and contact fields are the ship's progress through a pad sequence, see Character::target.
+    pub target: usize,
+    pub contact: u64,
+    /// The sequence number of the last input the server applied to this ship.
+    pub acknowledged: u32,
+}
+
+/// The AsteroidState struct describes a single asteroid in a snapshot.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AsteroidState {
+    pub x: f32,
+    pub y: f32,
+    pub trajectory: f32,
+    pub orientation: f32,
+    pub size: f32,
+    pub speed: f32,
+}
+
+/// The PickupState struct describes a single pickup in a snapshot.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PickupState {
+    pub body: AsteroidState,
+    pub kind: PickupKind,
+}
+
+impl ShipState {
+    /// The to_character function rebuilds the ship described by the snapshot.
+    pub fn to_character(&self) -> Character {
+        let mut character = Character::new(self.x, self.y);
+        character.momentum = Momentum {
+            vertical: self.vertical,
+            horizontal: self.horizontal,
+        };
+        character.destroyed = self.destroyed;
+        character.landed = self.landed;
+        character.shield = self.shield;
+        character.cooldown = self.cooldown;
+        character.target = self.target;
+        character.contact = self.contact;
+        character
+    }
+}
+
+impl From<&Asteroid> for AsteroidState {
+    fn from(asteroid: &Asteroid) -> Self {
+        AsteroidState {
+            x: asteroid.coordinate.x,
+            y: asteroid.coordinate.y,
+            trajectory: asteroid.trajectory,
+            orientation: asteroid.orientation.0,
+            size: asteroid.scaling.x,
+            speed: asteroid.speed,
+        }
+    }
+}
+
+impl From<&AsteroidState> for Asteroid {
+    fn from(state: &AsteroidState) -> Self {
+        Asteroid {
+            coordinate: PointF::new(state.x, state.y),
+            trajectory: state.trajectory,
+            orientation: Degrees::new(state.orientation),
+            scaling: PointF::new(state.size, state.size),
+            speed: state.speed,
+            scripted: false,
+            hit_points: Asteroid::hit_points_for(state.size),
+            grazing: Vec::new(),
+            passed: Vec::new(),
+            id: 0,
+        }
+    }
+}
+
+impl From<&Pickup> for PickupState {
+    fn from(pickup: &Pickup) -> Self {
+        PickupState {
+            body: AsteroidState::from(&pickup.body),
+            kind: pickup.kind,
+        }
+    }
+}
+
+impl From<&PickupState> for Pickup {
+    fn from(state: &PickupState) -> Self {
+        Pickup {
+            body: Asteroid::from(&state.body),
+            kind: state.kind,
+        }
+    }
+}
+
+/// The Player struct is the server's record of a connected client. Players are stored in the same order as the
+/// ships in the server's world.
+struct Player {
+    id: u32,
+    address: SocketAddr,
+    last_seen: Instant,
+    /// The inputs field queues the inputs received from the client and not applied yet, with their sequence number.
+    /// Every tick flies the ship with the next one, the way the client predicted it, or with the last input applied
+    /// again when none arrived in time.
+    inputs: VecDeque<(u32, ShipInput)>,
+    input: ShipInput,
+    /// The received field is the sequence number of the latest input received, acknowledged the one of the input the
+    /// last tick flew the ship with.
+    received: u32,
+    acknowledged: u32,
+    /// The unreachable field is set once sending to the client failed, so that the failure is only reported once
+    /// until sending works again. A client that stays unreachable times out.
+    unreachable: bool,
+}
+
+/// The Server struct owns the authoritative simulation of a multiplayer game. Clients only send their inputs, the
+/// server flies their ships, advances the asteroids and sends everyone a snapshot after each tick.
+pub struct Server {
+    socket: UdpSocket,
+    level: Level,
+    world: World,
+    players: Vec<Player>,
+    next_id: u32,
+    round: u32,
+    round_over: Option<Instant>,
+}
+
+impl Server {
+    /// The bind function opens the server's socket on the given address, for example "127.0.0.1:7878". Every round
+    /// is played on the given level.
+    pub fn bind(address: &str, level: Level) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Server {
+            socket,
+            world: World::from_level(&level, 0, RandomNumberGenerator::new()),
+            level,
+            players: Vec::new(),
+            next_id: 1,
+            round: 0,
+            round_over: None,
+        })
+    }
+
+    /// The run function is the server's main loop, it never returns. An error receiving a datagram or sending one to a
+    /// client is reported on stderr and the game goes on for the other clients.
+    pub fn run(&mut self) -> ! {
+        let mut next_tick = Instant::now();
+        loop {
+            self.receive();
+
+            let now = Instant::now();
+            if now >= next_tick {
+                self.tick(now);
+                self.broadcast();
+                next_tick += SERVER_TICK;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// The receive function handles every datagram waiting on the socket. Inputs are only queued, the ships are flown
+    /// with one of them per tick, so that sending inputs faster doesn't make a ship fly faster.
+    fn receive(&mut self) {
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            let (length, address) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return,
+                // An unreachable client shows up as an error on the next receive, it times out if it is really gone
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionRefused
+                    ) =>
+                {
+                    continue
+                }
+                // Anything else, such as a datagram too large for the buffer, is reported and the datagrams left are
+                // read on the next loop
+                Err(error) => {
+                    eprintln!("Could not receive a datagram: {}", error);
+                    return;
+                }
+            };
+            let message: ClientMessage = match serde_json::from_slice(&buffer[..length]) {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+
+            match message {
+                ClientMessage::Join => self.join(address),
+                ClientMessage::Input { sequence, input } => {
+                    if let Some(index) = self.index_of(address) {
+                        let player = &mut self.players[index];
+                        player.last_seen = Instant::now();
+                        if sequence > player.received {
+                            player.received = sequence;
+                            player.inputs.push_back((sequence, input));
+                            if player.inputs.len() > MAX_QUEUED_INPUTS {
+                                player.inputs.pop_front();
+                            }
+                        }
+                    }
+                }
+                ClientMessage::Leave => {
+                    if let Some(index) = self.index_of(address) {
+                        println!("Player {} left", self.players[index].id);
+                        self.remove(index);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The join function gives a ship to a new client, or repeats the welcome message to a client that already
+    /// joined but did not receive it.
+    fn join(&mut self, address: SocketAddr) {
+        let id = match self.index_of(address) {
+            Some(index) => self.players[index].id,
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                self.players.push(Player {
+                    id,
+                    address,
+                    last_seen: Instant::now(),
+                    inputs: VecDeque::new(),
+                    input: ShipInput::default(),
+                    received: 0,
+                    acknowledged: 0,
+                    unreachable: false,
+                });
+                let arena = &self.world.arena;
+                let character = World::spawn_character(arena.width, arena.height);
+                self.world.characters.push(character);
+                println!("Player {} joined from {}", id, address);
+                id
+            }
+        };
+        let welcome = ServerMessage::Welcome {
+            player: id,
+            round: self.round,
+            arena: Box::new(self.world.arena.clone()),
+        };
+        if let Err(error) = self.send(&welcome, address) {
+            eprintln!("Could not welcome player {}: {}", id, error);
+        }
+    }
+
+    /// The tick function flies every ship with its next input and advances the world, drops silent clients and starts
+    /// a new round a few seconds after every ship was destroyed.
+    fn tick(&mut self, now: Instant) {
+        // Every silent client is dropped, one at a time so that the players and the ships stay in the same order
+        let mut index = 0;
+        while index < self.players.len() {
+            if now.duration_since(self.players[index].last_seen) > CLIENT_TIMEOUT {
+                println!("Player {} timed out", self.players[index].id);
+                self.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+
+        if self.players.is_empty() {
+            return;
+        }
+
+        if self.world.is_over() {
+            let round_over = *self.round_over.get_or_insert(now);
+            if now.duration_since(round_over) > ROUND_DELAY {
+                self.world = World::from_level(
+                    &self.level,
+                    self.players.len(),
+                    RandomNumberGenerator::new(),
+                );
+                self.round += 1;
+                self.round_over = None;
+            }
+            return;
+        }
+
+        // Nobody reads the events on the server, the next step drops them
+        for player in self.players.iter_mut() {
+            if let Some((sequence, input)) = player.inputs.pop_front() {
+                player.acknowledged = sequence;
+                player.input = input;
+            }
+        }
+        let inputs: Vec<ShipInput> = self.players.iter().map(|player| player.input).collect();
+        self.world.step(&inputs);
+    }
+
+    /// The broadcast function sends the current snapshot to every client. A client that can't be sent to is skipped,
+    /// and reported the first time.
+    fn broadcast(&mut self) {
+        let message = ServerMessage::Snapshot(Box::new(self.snapshot()));
+        let bytes = match serde_json::to_vec(&message) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                eprintln!("Could not serialize the snapshot: {}", error);
+                return;
+            }
+        };
+        for player in self.players.iter_mut() {
+            match Server::send_bytes(&self.socket, &bytes, player.address) {
+                Ok(()) => player.unreachable = false,
+                Err(error) => {
+                    if !player.unreachable {
+                        eprintln!("Could not send the snapshot to player {}: {}", player.id, error);
+                    }
+                    player.unreachable = true;
+                }
+            }
+        }
+    }
+
+    /// The snapshot function copies the world into a Snapshot.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            round: self.round,
+            tick: self.world.tick,
+            score: self.world.score,
+            ships: self
+                .players
+                .iter()
+                .zip(self.world.characters.iter())
+                .map(|(player, character)| ShipState {
+                    player: player.id,
+                    x: character.coordinate.x,
+                    y: character.coordinate.y,
+                    vertical: character.momentum.vertical,
+                    horizontal: character.momentum.horizontal,
+                    destroyed: character.destroyed,
+                    landed: character.landed,
+                    shield: character.shield,
+                    cooldown: character.cooldown,
+                    target: character.target,
+                    contact: character.contact,
+                    acknowledged: player.acknowledged,
+                })
+                .collect(),
+            asteroids: self.world.asteroids.iter().map(AsteroidState::from).collect(),
+            pickups: self.world.pickups.iter().map(PickupState::from).collect(),
+            projectiles: self.world.projectiles.clone(),
+            slow_time: self.world.slow_time,
+            wind: self.world.arena.wind,
+            terrain: self.world.arena.terrain.heights.clone(),
+            pads: self
+                .world
+                .arena
+                .landing_pads
+                .iter()
+                .map(|landing_pad| PadState {
+                    x: landing_pad.x,
+                    heigth: landing_pad.heigth,
+                    width: landing_pad.pad_width,
+                    velocity: landing_pad.velocity,
+                })
+                .collect(),
+        }
+    }
+
+    fn send(&self, message: &ServerMessage, address: SocketAddr) -> io::Result<()> {
+        let bytes = serde_json::to_vec(message).map_err(io::Error::from)?;
+        Server::send_bytes(&self.socket, &bytes, address)
+    }
+
+    /// The send_bytes function sends a datagram to the given address. A full send buffer drops the datagram, like the
+    /// network could.
+    fn send_bytes(socket: &UdpSocket, bytes: &[u8], address: SocketAddr) -> io::Result<()> {
+        match socket.send_to(bytes, address) {
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+
+    fn index_of(&self, address: SocketAddr) -> Option<usize> {
+        self.players.iter().position(|player| player.address == address)
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.players.remove(index);
+        self.world.characters.remove(index);
+    }
+}
+
+/// The Client struct connects the game window to a server. It keeps a copy of the server's world, in which the
+/// player's own ship is predicted from the inputs the server has not applied yet, and the other ships are
+/// interpolated between the last two snapshots.
+pub struct Client {
+    socket: UdpSocket,
+    /// The player field holds the id of the ship controlled by this client, once the server welcomed it.
+    pub player: Option<u32>,
+    /// The world field is the client's copy of the server's world, it is the one being rendered.
+    pub world: World,
+    /// The round field is the round of the arena in the client's world, None until the server welcomed the client.
+    round: Option<u32>,
+    sequence: u32,
+    pending: VecDeque<(u32, ShipInput)>,
+    previous: Option<(Instant, Snapshot)>,
+    latest: Option<(Instant, Snapshot)>,
+    last_join: Instant,
+}
+
+impl Client {
+    /// The connect function opens a socket towards the server's address and asks it for a ship.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(address)?;
+        socket.set_nonblocking(true)?;
+
+        let client = Client {
+            socket,
+            player: None,
+            world: World::new(0),
+            round: None,
+            sequence: 0,
+            pending: VecDeque::new(),
+            previous: None,
+            latest: None,
+            last_join: Instant::now(),
+        };
+        client.send(&ClientMessage::Join);
+        Ok(client)
+    }
+
+    /// The send_input function sends one frame of input to the server, it should be called once per server tick. The
+    /// input is immediately applied to the player's own ship, the same way the server will, so that the controls feel
+    /// as responsive as offline.
+    pub fn send_input(&mut self, input: ShipInput) {
+        if self.player.is_none() {
+            if self.last_join.elapsed() > JOIN_RETRY {
+                self.send(&ClientMessage::Join);
+                self.last_join = Instant::now();
+            }
+            return;
+        }
+
+        self.sequence += 1;
+        self.pending.push_back((self.sequence, input));
+        self.send(&ClientMessage::Input {
+            sequence: self.sequence,
+            input,
+        });
+
+        if let Some(index) = self.own_index() {
+            self.world.fly(index, &input);
+        }
+    }
+
+    /// The receive function reads every datagram sent by the server, and rebuilds the world from the latest snapshot.
+    pub fn receive(&mut self) {
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let mut updated = false;
+        while let Ok(length) = self.socket.recv(&mut buffer) {
+            match serde_json::from_slice(&buffer[..length]) {
+                Ok(ServerMessage::Welcome {
+                    player,
+                    round,
+                    arena,
+                }) => {
+                    self.player = Some(player);
+                    self.round = Some(round);
+                    self.world.visited = vec![false; arena.landing_pads.len()];
+                    self.world.arena = *arena;
+                }
+                Ok(ServerMessage::Snapshot(snapshot)) => {
+                    let newer = self
+                        .latest
+                        .as_ref()
+                        .is_none_or(|(_, latest)| snapshot.tick != latest.tick);
+                    if newer {
+                        self.previous = self.latest.take();
+                        self.latest = Some((Instant::now(), *snapshot));
+                        updated = true;
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+        if updated {
+            self.reconcile();
+        }
+    }
+
+    /// The reconcile function copies the latest snapshot into the client's world. The player's own ship is reset to
+    /// the server's state, then every input the server has not acknowledged yet is applied again on top of it. A
+    /// snapshot of another round asks the server for the new arena.
+    fn reconcile(&mut self) {
+        let Some((_, snapshot)) = self.latest.as_ref() else {
+            return;
+        };
+
+        if Some(snapshot.round) == self.round {
+            let arena = &mut self.world.arena;
+            arena.wind = snapshot.wind;
+            arena.terrain.heights = snapshot.terrain.clone();
+            for (landing_pad, pad) in arena.landing_pads.iter_mut().zip(snapshot.pads.iter()) {
+                landing_pad.x = pad.x;
+                landing_pad.heigth = pad.heigth;
+                landing_pad.pad_width = pad.width;
+                landing_pad.velocity = pad.velocity;
+            }
+        } else if self.last_join.elapsed() > JOIN_RETRY {
+            self.send(&ClientMessage::Join);
+            self.last_join = Instant::now();
+        }
+
+        self.world.events.clear();
+        self.world.tick = snapshot.tick;
+        self.world.score = snapshot.score;
+        self.world.asteroids = snapshot.asteroids.iter().map(Asteroid::from).collect();
+        self.world.pickups = snapshot.pickups.iter().map(Pickup::from).collect();
+        self.world.projectiles = snapshot.projectiles.clone();
+        self.world.slow_time = snapshot.slow_time;
+        self.world.characters = snapshot.ships.iter().map(ShipState::to_character).collect();
+
+        let Some(own) = snapshot
+            .ships
+            .iter()
+            .find(|ship| Some(ship.player) == self.player)
+        else {
+            return;
+        };
+        self.pending
+            .retain(|(sequence, _)| *sequence > own.acknowledged);
+
+        if let Some(index) = self.own_index() {
+            for (_, input) in self.pending.iter() {
+                self.world.fly(index, input);
+            }
+        }
+    }
+
+    /// The interpolate function places the other players' ships between their position in the previous and in the
+    /// latest snapshot, according to the time elapsed since the latest one arrived. This trades a snapshot of delay
+    /// for smooth movement.
+    pub fn interpolate(&mut self) {
+        let (Some((previous_time, previous)), Some((latest_time, latest))) =
+            (self.previous.as_ref(), self.latest.as_ref())
+        else {
+            return;
+        };
+
+        let interval = latest_time.duration_since(*previous_time).as_secs_f32();
+        let progress = if interval > 0.0 {
+            (latest_time.elapsed().as_secs_f32() / interval).min(1.0)
+        } else {
+            1.0
+        };
+
+        for (ship, character) in latest.ships.iter().zip(self.world.characters.iter_mut()) {
+            if Some(ship.player) == self.player {
+                continue;
+            }
+            if let Some(before) = previous.ships.iter().find(|before| before.player == ship.player) {
+                character.coordinate.x = before.x + (ship.x - before.x) * progress;
+                character.coordinate.y = before.y + (ship.y - before.y) * progress;
+            }
+        }
+    }
+
+    /// The own_character function returns the ship controlled by this client, if the server already gave it one.
+    pub fn own_character(&self) -> Option<&Character> {
+        self.own_index()
+            .and_then(|index| self.world.characters.get(index))
+    }
+
+    /// The leave function tells the server that this client is quitting.
+    pub fn leave(&self) {
+        self.send(&ClientMessage::Leave);
+    }
+
+    fn own_index(&self) -> Option<usize> {
+        let (_, snapshot) = self.latest.as_ref()?;
+        snapshot
+            .ships
+            .iter()
+            .position(|ship| Some(ship.player) == self.player)
+    }
+
+    /// The send function sends a message to the server. Datagrams can be lost anyway, so errors are ignored: the
+    /// game keeps running and the next snapshot will tell if the server is still there.
+    fn send(&self, message: &ClientMessage) {
+        if let Ok(bytes) = serde_json::to_vec(message) {
+            let _ = self.socket.send(&bytes);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::bindings::{Action, Bindings};
+use crate::i18n::Language;
+use crate::storage;
+use crate::theme::{Theme, DEFAULT_THEME};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The name of the options' save file, in the data directory.
+const SAVE_FILE: &str = "options.json";
+
+/// The version of the options' save file written by this version of the game.
+const SAVE_VERSION: u32 = 1;
+
+/// The Options struct holds the player's choices of how the game looks and is played, it is saved in the data directory every time
+/// they change. Fields added by later versions must have a default, so that older files still load.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Options {
+    pub version: u32,
+    /// The theme field is the name of the chosen theme, see Theme::registry.
+    pub theme: String,
+    /// The reduced_motion field stops the asteroids from spinning and the screens from fading in.
+    pub reduced_motion: bool,
+    /// The language field is the language of the game's texts.
+    pub language: Language,
+    /// The bindings field maps the rebound actions to the name of their key, such as `"Pause": "Escape"`, the other
+    /// actions keep their default key. They are only set by editing the save file.
+    pub bindings: BTreeMap<Action, String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            version: SAVE_VERSION,
+            theme: DEFAULT_THEME.to_string(),
+            reduced_motion: false,
+            language: Language::default(),
+            bindings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Options {
+    /// The load function reads the options from the data directory. A file written by a newer version of the game is
+    /// ignored rather than misread.
+    pub fn load() -> Self {
+        let options: Options = storage::load(SAVE_FILE);
+        if options.version > SAVE_VERSION {
+            eprintln!(
+                "Ignoring {}: version {} is not supported",
+                SAVE_FILE, options.version
+            );
+            return Options::default();
+        }
+        options
+    }
+
+    /// The save function writes the options to the data directory.
+    pub fn save(&self) {
+        if let Err(error) = storage::save(SAVE_FILE, self) {
+            eprintln!("Could not save the options: {}", error);
+        }
+    }
+
+    /// The theme function returns the chosen theme, with the motion options applied to it.
+    pub fn theme(&self) -> Theme {
+        Theme {
+            reduced_motion: self.reduced_motion,
+            ..Theme::find(&self.theme)
+        }
+    }
+
+    /// The cycle_theme function chooses the theme after the current one in the registry, or the one before it if
+    /// backwards is set, and returns it.
+    pub fn cycle_theme(&mut self, backwards: bool) -> Theme {
+        let names: Vec<String> = Theme::registry()
+            .into_iter()
+            .map(|theme| theme.name)
+            .collect();
+        let current = names
+            .iter()
+            .position(|name| *name == self.theme)
+            .unwrap_or(0);
+        let next = if backwards {
+            (current + names.len() - 1) % names.len()
+        } else {
+            (current + 1) % names.len()
+        };
+        self.theme = names[next].clone();
+        self.theme()
+    }
+
+    /// The key_bindings function returns the key bound to every action, with the rebound actions applied.
+    pub fn key_bindings(&self) -> Bindings {
+        Bindings::new(&self.bindings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bracket_lib::prelude::VirtualKeyCode;
+
+    #[test]
+    fn options_round_trip() {
+        let options = Options {
+            theme: "Protanopia".to_string(),
+            reduced_motion: true,
+            language: Language::French,
+            bindings: BTreeMap::from([(Action::Pause, "P".to_string())]),
+            ..Options::default()
+        };
+        let text = serde_json::to_string(&options).unwrap();
+        let loaded: Options = serde_json::from_str(&text).unwrap();
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!(loaded.theme, "Protanopia");
+        assert!(loaded.reduced_motion);
+        assert_eq!(loaded.language, Language::French);
+        assert_eq!(loaded.bindings, options.bindings);
+        assert_eq!(loaded.key_bindings().key(Action::Pause), VirtualKeyCode::P);
+    }
+
+    #[test]
+    fn missing_options_get_their_default() {
+        let loaded: Options = serde_json::from_str(r#"{"version": 1, "language": "French"}"#).unwrap();
+        assert_eq!(loaded.theme, DEFAULT_THEME);
+        assert!(!loaded.reduced_motion);
+        assert_eq!(loaded.language, Language::French);
+        assert!(loaded.bindings.is_empty());
+    }
+
+    #[test]
+    fn the_theme_carries_the_motion_option() {
+        let options = Options {
+            theme: "Deuteranopia".to_string(),
+            reduced_motion: true,
+            ..Options::default()
+        };
+        let theme = options.theme();
+        assert_eq!(theme.name, "Deuteranopia");
+        assert!(theme.reduced_motion);
+    }
+}
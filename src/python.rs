@@ -0,0 +1,93 @@
+use crate::env::{self, EnvConfig, RewardConfig};
+use crate::level::Level;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The Step type is what step returns to Python: the observation, the reward, whether the episode is over and the info.
+type Step = (Vec<f32>, f32, bool, HashMap<String, f64>);
+
+/// The PyRoverEnv class exposes env::RoverEnv to Python as rust_rover.RoverEnv. Observations are returned as flat
+/// lists of floats (see env::Observation::to_vec) and actions are the discrete indexes of env::RoverEnv::action.
+#[pyclass(name = "RoverEnv", unsendable)]
+struct PyRoverEnv {
+    env: env::RoverEnv,
+}
+
+#[pymethods]
+impl PyRoverEnv {
+    // The keyword arguments mirror the fields of EnvConfig and RewardConfig, they are passed by name from Python
+    #[allow(clippy::too_many_arguments)]
+    #[new]
+    #[pyo3(signature = (nearest = 5, max_steps = 10_000, survival = 0.01, landing = 1.0, score = 1.0, crash = -10.0, win = 10.0, level = None))]
+    fn new(
+        nearest: usize,
+        max_steps: u64,
+        survival: f32,
+        landing: f32,
+        score: f32,
+        crash: f32,
+        win: f32,
+        level: Option<String>,
+    ) -> PyResult<Self> {
+        let level = match level {
+            Some(path) => Level::load(Path::new(&path)).map_err(PyValueError::new_err)?,
+            None => Level::default(),
+        };
+        Ok(PyRoverEnv {
+            env: env::RoverEnv::new(EnvConfig {
+                nearest,
+                max_steps,
+                rewards: RewardConfig {
+                    survival,
+                    landing,
+                    score,
+                    crash,
+                    win,
+                },
+                level,
+            }),
+        })
+    }
+
+    /// The size of the observation lists returned by reset and step.
+    #[getter]
+    fn observation_size(&self) -> usize {
+        env::Observation::SHIP_FEATURES
+            + env::Observation::PAD_FEATURES
+            + self.env.config.nearest * env::Observation::ASTEROID_FEATURES
+    }
+
+    /// The number of discrete actions accepted by step.
+    #[getter]
+    fn action_count(&self) -> u8 {
+        self.env.action_count()
+    }
+
+    #[pyo3(signature = (seed = 0))]
+    fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.env.reset(seed).to_vec(self.env.config.nearest)
+    }
+
+    /// Raises ValueError for an action of action_count or more.
+    fn step(&mut self, action: u8) -> PyResult<Step> {
+        let action = self.env.action(action).map_err(PyValueError::new_err)?;
+        let (observation, reward, done, info) = self.env.step(action);
+        let info = HashMap::from([
+            ("score".to_string(), info.score as f64),
+            ("tick".to_string(), info.tick as f64),
+            ("landings".to_string(), info.landings as f64),
+            ("truncated".to_string(), if info.truncated { 1.0 } else { 0.0 }),
+            ("won".to_string(), if info.won { 1.0 } else { 0.0 }),
+        ]);
+        Ok((observation.to_vec(self.env.config.nearest), reward, done, info))
+    }
+}
+
+/// The rust_rover Python module, built with `maturin develop --features python`.
+#[pymodule]
+fn rust_rover(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyRoverEnv>()?;
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use crate::character::{Character, Momentum};
+use crate::environment::Vector;
+use crate::hazards::{Asteroid, Pickup, PickupKind};
+use crate::level::{Level, SpawnDefinition};
+use crate::storage;
+use crate::weapon::Projectile;
+use crate::world::{Arena, World};
+use bracket_lib::prelude::{Degrees, PointF, RandomNumberGenerator};
+use serde::{Deserialize, Serialize};
+
+/// The name of the saved run's file, in the data directory.
+const SAVE_FILE: &str = "run.json";
+
+/// The version of the saved run's file written by this version of the game.
+const SAVE_VERSION: u32 = 1;
+
+/// The SavedShip struct is the full state of a ship, unlike the multiplayer snapshots which only send what is drawn.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedShip {
+    pub x: f32,
+    pub y: f32,
+    pub vertical: f32,
+    pub horizontal: f32,
+    pub force: Vector,
+    pub destroyed: bool,
+    pub landed: bool,
+    pub shield: u64,
+    pub cooldown: u32,
+    #[serde(default)]
+    pub thrusting: bool,
+    #[serde(default)]
+    pub combo: usize,
+    #[serde(default)]
+    pub combo_timer: u64,
+    #[serde(default)]
+    pub target: usize,
+    #[serde(default)]
+    pub contact: u64,
+    #[serde(default)]
+    pub splits: Vec<u64>,
+}
+
+impl From<&Character> for SavedShip {
+    fn from(character: &Character) -> Self {
+        SavedShip {
+            x: character.coordinate.x,
+            y: character.coordinate.y,
+            vertical: character.momentum.vertical,
+            horizontal: character.momentum.horizontal,
+            force: character.force,
+            destroyed: character.destroyed,
+            landed: character.landed,
+            shield: character.shield,
+            cooldown: character.cooldown,
+            thrusting: character.thrusting,
+            combo: character.combo,
+            combo_timer: character.combo_timer,
+            target: character.target,
+            contact: character.contact,
+            splits: character.splits.clone(),
+        }
+    }
+}
+
+impl From<&SavedShip> for Character {
+    fn from(ship: &SavedShip) -> Self {
+        let mut character = Character::new(ship.x, ship.y);
+        character.momentum = Momentum {
+            vertical: ship.vertical,
+            horizontal: ship.horizontal,
+        };
+        character.force = ship.force;
+        character.destroyed = ship.destroyed;
+        character.landed = ship.landed;
+        character.shield = ship.shield;
+        character.cooldown = ship.cooldown;
+        character.thrusting = ship.thrusting;
+        character.combo = ship.combo.max(1);
+        character.combo_timer = ship.combo_timer;
+        character.target = ship.target;
+        character.contact = ship.contact;
+        character.splits = ship.splits.clone();
+        character
+    }
+}
+
+/// The SavedAsteroid struct is the full state of an asteroid, its hit points and the ships it is grazing included.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedAsteroid {
+    pub x: f32,
+    pub y: f32,
+    pub trajectory: f32,
+    pub orientation: f32,
+    pub width: f32,
+    pub height: f32,
+    pub speed: f32,
+    pub scripted: bool,
+    pub hit_points: u32,
+    #[serde(default)]
+    pub grazing: Vec<usize>,
+    #[serde(default)]
+    pub passed: Vec<usize>,
+    #[serde(default)]
+    pub id: u64,
+}
+
+impl From<&Asteroid> for SavedAsteroid {
+    fn from(asteroid: &Asteroid) -> Self {
+        SavedAsteroid {
+            x: asteroid.coordinate.x,
+            y: asteroid.coordinate.y,
+            trajectory: asteroid.trajectory,
+            orientation: asteroid.orientation.0,
+            width: asteroid.scaling.x,
+            height: asteroid.scaling.y,
+            speed: asteroid.speed,
+            scripted: asteroid.scripted,
+            hit_points: asteroid.hit_points,
+            grazing: asteroid.grazing.clone(),
+            passed: asteroid.passed.clone(),
+            id: asteroid.id,
+        }
+    }
+}
+
+impl From<&SavedAsteroid> for Asteroid {
+    fn from(asteroid: &SavedAsteroid) -> Self {
+        Asteroid {
+            coordinate: PointF::new(asteroid.x, asteroid.y),
+            trajectory: asteroid.trajectory,
+            orientation: Degrees::new(asteroid.orientation),
+            scaling: PointF::new(asteroid.width, asteroid.height),
+            speed: asteroid.speed,
+            scripted: asteroid.scripted,
+            hit_points: asteroid.hit_points,
+            grazing: asteroid.grazing.clone(),
+            passed: asteroid.passed.clone(),
+            id: asteroid.id,
+        }
+    }
+}
+
+/// The SavedPickup struct is the full state of a falling pickup.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedPickup {
+    pub body: SavedAsteroid,
+    pub kind: PickupKind,
+}
+
+/// The SavedRun struct is a game left before its end, saved in the data directory to be continued from the main menu.
+/// It holds the level the game was started from and everything of the world that changes while playing. The random
+/// number generator can't be saved as it is, so a seed is drawn from it when saving and both the saved run and the
+/// game left reseed their generator with it: the continued game plays out exactly as the left one would have.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedRun {
+    pub version: u32,
+    pub level: Level,
+    pub seed: u64,
+    pub tick: u64,
+    pub next_id: u64,
+    pub score: usize,
+    pub avoided: usize,
+    pub progress: usize,
+    pub slow_time: u64,
+    pub visited: Vec<bool>,
+    /// The arena field is None in a file missing it, the arena of the level is then used.
+    pub arena: Option<Arena>,
+    pub ships: Vec<SavedShip>,
+    pub asteroids: Vec<SavedAsteroid>,
+    pub pickups: Vec<SavedPickup>,
+    pub projectiles: Vec<Projectile>,
+    pub spawns: Vec<SpawnDefinition>,
+}
+
+impl Default for SavedRun {
+    fn default() -> Self {
+        SavedRun {
+            version: SAVE_VERSION,
+            level: Level::default(),
+            seed: 0,
+            tick: 0,
+            next_id: 0,
+            score: 0,
+            avoided: 0,
+            progress: 0,
+            slow_time: 0,
+            visited: Vec::new(),
+            arena: None,
+            ships: Vec::new(),
+            asteroids: Vec::new(),
+            pickups: Vec::new(),
+            projectiles: Vec::new(),
+            spawns: Vec::new(),
+        }
+    }
+}
+
+impl SavedRun {
+    /// The new function captures the game being played in the given world, started from the given level. The world's
+    /// random number generator is reseeded with the saved seed, see SavedRun.
+    pub fn new(level: &Level, world: &mut World) -> Self {
+        let seed = world.random.next_u64();
+        world.random = RandomNumberGenerator::seeded(seed);
+        SavedRun {
+            version: SAVE_VERSION,
+            level: level.clone(),
+            seed,
+            tick: world.tick,
+            next_id: world.next_id,
+            score: world.score,
+            avoided: world.avoided,
+            progress: world.progress,
+            slow_time: world.slow_time,
+            visited: world.visited.clone(),
+            arena: Some(world.arena.clone()),
+            ships: world.characters.iter().map(SavedShip::from).collect(),
+            asteroids: world.asteroids.iter().map(SavedAsteroid::from).collect(),
+            pickups: world
+                .pickups
+                .iter()
+                .map(|pickup| SavedPickup {
+                    body: SavedAsteroid::from(&pickup.body),
+                    kind: pickup.kind,
+                })
+                .collect(),
+            projectiles: world.projectiles.clone(),
+            spawns: world.spawns.clone(),
+        }
+    }
+
+    /// The world function rebuilds the world of the saved game, the way it was when it was saved.
+    pub fn world(&self) -> World {
+        let mut world = World::from_level(
+            &self.level,
+            self.ships.len(),
+            RandomNumberGenerator::seeded(self.seed),
+        );
+        world.random = RandomNumberGenerator::seeded(self.seed);
+        world.tick = self.tick;
+        world.next_id = self.next_id;
+        world.score = self.score;
+        world.avoided = self.avoided;
+        world.progress = self.progress;
+        world.slow_time = self.slow_time;
+        world.visited = self.visited.clone();
+        if let Some(arena) = self.arena.as_ref() {
+            world.arena = arena.clone();
+        }
+        world.characters = self.ships.iter().map(Character::from).collect();
+        world.asteroids = self.asteroids.iter().map(Asteroid::from).collect();
+        world.pickups = self
+            .pickups
+            .iter()
+            .map(|pickup| Pickup {
+                body: Asteroid::from(&pickup.body),
+                kind: pickup.kind,
+            })
+            .collect();
+        world.projectiles = self.projectiles.clone();
+        world.spawns = self.spawns.clone();
+        world
+    }
+
+    /// The load function reads the saved run from the data directory, if there is one. A file written by a newer
+    /// version of the game is ignored rather than misread.
+    pub fn load() -> Option<SavedRun> {
+        let run: Option<SavedRun> = storage::load(SAVE_FILE);
+        let run = run?;
+        if run.version > SAVE_VERSION {
+            eprintln!(
+                "Ignoring {}: version {} is not supported",
+                SAVE_FILE, run.version
+            );
+            return None;
+        }
+        // A run without ships can't be continued, it was cut short or written by hand
+        (!run.ships.is_empty()).then_some(run)
+    }
+
+    /// The save function writes the saved run to the data directory, replacing the previous one.
+    pub fn save(&self) {
+        if let Err(error) = storage::save(SAVE_FILE, self) {
+            eprintln!("Could not save the run: {}", error);
+        }
+    }
+
+    /// The remove function deletes the saved run from the data directory, once the game it was saved from is over.
+    pub fn remove() {
+        if let Err(error) = storage::remove(SAVE_FILE) {
+            eprintln!("Could not remove the saved run: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::ShipInput;
+
+    fn input(tick: u64) -> ShipInput {
+        ShipInput {
+            up: tick % 4 < 3,
+            left: tick % 60 < 15,
+            right: tick % 90 > 70,
+            fire: false,
+        }
+    }
+
+    /// The ships function sums up the ships of the world, to compare two of them.
+    fn ships(world: &World) -> Vec<(f32, f32, bool, usize, usize)> {
+        world
+            .characters
+            .iter()
+            .map(|character| {
+                (
+                    character.coordinate.x,
+                    character.coordinate.y,
+                    character.destroyed,
+                    character.combo,
+                    character.target,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_continued_run_plays_out_like_the_one_left() {
+        let level = Level::default();
+        let mut left = World::from_level(&level, 1, RandomNumberGenerator::seeded(9));
+        for tick in 0..300 {
+            left.step(&[input(tick)]);
+        }
+        let run = SavedRun::new(&level, &mut left);
+        let text = serde_json::to_string(&run).unwrap();
+        let loaded: SavedRun = serde_json::from_str(&text).unwrap();
+        let mut continued = loaded.world();
+
+        assert_eq!(continued.tick, left.tick);
+        assert_eq!(continued.score, left.score);
+        assert_eq!(continued.asteroids.len(), left.asteroids.len());
+        for tick in 300..900 {
+            left.step(&[input(tick)]);
+            continued.step(&[input(tick)]);
+            assert_eq!(continued.events, left.events, "tick {}", tick);
+            assert_eq!(ships(&continued), ships(&left), "tick {}", tick);
+            assert_eq!(continued.score, left.score);
+        }
+    }
+
+    #[test]
+    fn ships_round_trip() {
+        let mut character = Character::new(12.5, 30.0);
+        character.momentum.vertical = -0.3;
+        character.shield = 40;
+        character.combo = 3;
+        character.combo_timer = 17;
+        character.target = 2;
+        character.contact = 5;
+        character.splits = vec![100, 250];
+        let text = serde_json::to_string(&SavedShip::from(&character)).unwrap();
+        let loaded = Character::from(&serde_json::from_str::<SavedShip>(&text).unwrap());
+        assert_eq!((loaded.coordinate.x, loaded.coordinate.y), (12.5, 30.0));
+        assert_eq!(loaded.momentum.vertical, -0.3);
+        assert_eq!(loaded.shield, 40);
+        assert_eq!((loaded.combo, loaded.combo_timer), (3, 17));
+        assert_eq!((loaded.target, loaded.contact), (2, 5));
+        assert_eq!(loaded.splits, vec![100, 250]);
+    }
+
+    #[test]
+    fn older_ships_get_their_default() {
+        let text = r#"{"x": 1.0, "y": 2.0, "vertical": 0.0, "horizontal": 0.0, "force": {"x": 0.0, "y": 0.0},
+            "destroyed": false, "landed": true, "shield": 0, "cooldown": 0}"#;
+        let loaded = Character::from(&serde_json::from_str::<SavedShip>(text).unwrap());
+        assert!(loaded.landed);
+        assert_eq!(loaded.combo, 1);
+        assert_eq!(loaded.target, 0);
+        assert!(loaded.splits.is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::storage;
+use crate::world::{DeathCause, WorldEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The name of the statistics' save file, in the data directory.
+const SAVE_FILE: &str = "statistics.json";
+
+/// The version of the statistics' save file written by this version of the game.
+const SAVE_VERSION: u32 = 1;
+
+/// The Statistics struct accumulates what the player did across every game they played, it is saved in the data
+/// directory at the end of each game. Fields added by later versions must have a default, so that older files still
+/// load.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Statistics {
+    pub version: u32,
+    pub games_played: u64,
+    /// The flight_time field is the time spent playing, in seconds, pauses excluded.
+    pub flight_time: f64,
+    pub asteroids_avoided: u64,
+    pub deaths: BTreeMap<DeathCause, u64>,
+    pub landings: u64,
+    /// The fuel_burned field counts one unit of fuel for every thruster fired during a frame.
+    pub fuel_burned: u64,
+    /// The best_scores field maps the name of each game mode played, see Statistics::mode, to its best score.
+    pub best_scores: BTreeMap<String, usize>,
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Statistics {
+            version: SAVE_VERSION,
+            games_played: 0,
+            flight_time: 0.0,
+            asteroids_avoided: 0,
+            deaths: BTreeMap::new(),
+            landings: 0,
+            fuel_burned: 0,
+            best_scores: BTreeMap::new(),
+        }
+    }
+}
+
+impl Statistics {
+    /// The load function reads the statistics from the data directory. A file written by a newer version of the game
+    /// is ignored rather than misread.
+    pub fn load() -> Self {
+        let statistics: Statistics = storage::load(SAVE_FILE);
+        if statistics.version > SAVE_VERSION {
+            eprintln!(
+                "Ignoring {}: version {} is not supported",
+                SAVE_FILE, statistics.version
+            );
+            return Statistics::default();
+        }
+        statistics
+    }
+
+    /// The save function writes the statistics to the data directory.
+    pub fn save(&self) {
+        if let Err(error) = storage::save(SAVE_FILE, self) {
+            eprintln!("Could not save the statistics: {}", error);
+        }
+    }
+
+    /// The mode function returns the name under which the best score of a level is kept, the endless game is a level
+    /// without a name.
+    pub fn mode(level_name: &str) -> String {
+        if level_name.is_empty() {
+            "Endless".to_string()
+        } else {
+            level_name.to_string()
+        }
+    }
+
+    /// The observe function adds an event of the player's ship (ship 0) to the statistics.
+    pub fn observe(&mut self, event: &WorldEvent) {
+        match event {
+            WorldEvent::Thrust { ship: 0, input } => {
+                self.fuel_burned += [input.up, input.left, input.right]
+                    .iter()
+                    .filter(|fired| **fired)
+                    .count() as u64;
+            }
+            WorldEvent::Landed { ship: 0, .. } => self.landings += 1,
+            WorldEvent::Avoided { .. } => self.asteroids_avoided += 1,
+            WorldEvent::Destroyed { ship: 0, cause, .. } => {
+                *self.deaths.entry(*cause).or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// The finish_game function counts a finished game and keeps its score if it is the best of its mode.
+    pub fn finish_game(&mut self, mode: String, score: usize) {
+        self.games_played += 1;
+        let best = self.best_scores.entry(mode).or_default();
+        *best = (*best).max(score);
+    }
+}
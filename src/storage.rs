@@ -0,0 +1,59 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// The environment variable which can point the game to another directory for its save files.
+pub const DATA_DIR_VARIABLE: &str = "RUST_ROVER_DATA";
+
+/// The directory holding the save files when RUST_ROVER_DATA is not set, relative to where the game is started.
+const DEFAULT_DATA_DIR: &str = "saves";
+
+/// The data_dir function returns the directory where the game keeps its save files.
+pub fn data_dir() -> PathBuf {
+    std::env::var_os(DATA_DIR_VARIABLE)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_DIR))
+}
+
+/// The load function reads the save file with the given name from the data directory. A missing file is not an
+/// error, the default value is returned instead, so that a first launch starts from scratch. A file that can't be
+/// read is reported on stderr and ignored as well, rather than preventing the game from starting.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = data_dir().join(name);
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|error| {
+            eprintln!("Ignoring {}: {}", path.display(), error);
+            T::default()
+        }),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => T::default(),
+        Err(error) => {
+            eprintln!("Ignoring {}: {}", path.display(), error);
+            T::default()
+        }
+    }
+}
+
+/// The save function writes the value to the save file with the given name, creating the data directory if needed.
+/// The name can include subdirectories of the data directory, such as "ghosts/daily.json".
+/// The file is written next to its destination first, then renamed, so that a crash never leaves half a file.
+pub fn save<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    let path = data_dir().join(name);
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, serde_json::to_string_pretty(value)?)?;
+    fs::rename(temporary, path)
+}
+
+/// The remove function deletes the save file with the given name from the data directory. A missing file is not an
+/// error, there is nothing to remove.
+pub fn remove(name: &str) -> io::Result<()> {
+    match fs::remove_file(data_dir().join(name)) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,141 @@
+use crate::world::{World, WorldEvent};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The EventSink trait is implemented by everything the events of the games can be sent to, such as the event log.
+pub trait EventSink {
+    /// The record function is given every event of the games played, with the game and the tick it happened at.
+    fn record(&mut self, record: &EventRecord);
+
+    /// The flush function is called once the events of a step were all recorded, a sink that buffers them can write
+    /// them out.
+    fn flush(&mut self) {}
+}
+
+/// The EventRecord struct is an event with where it happened: the session is the time the game was launched at, in
+/// seconds since 1970, the game counts the games of the session from 1 and the tick is the number of ticks elapsed in
+/// the game once the event happened.
+#[derive(Clone, Debug, Serialize)]
+pub struct EventRecord<'a> {
+    pub session: u64,
+    pub game: u32,
+    pub tick: u64,
+    #[serde(flatten)]
+    pub event: &'a WorldEvent,
+}
+
+/// The EventBus struct sends the events of the world to the sinks subscribed to it, after every step. Without any
+/// sink, publishing the events costs nothing.
+pub struct EventBus {
+    session: u64,
+    game: u32,
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus {
+            session: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            game: 0,
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl EventBus {
+    /// The subscribe function adds a sink, it gets the events published from then on.
+    pub fn subscribe(&mut self, sink: impl EventSink + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// The start_game function tells the bus a new game starts, the events published next belong to it.
+    pub fn start_game(&mut self) {
+        self.game += 1;
+    }
+
+    /// The publish function sends the events of the world's last step to every sink. The Thrust events, pushed on every
+    /// tick a thruster fires for the statistics and the achievements, are kept out: ThrustStarted and ThrustStopped
+    /// tell the same once per burn.
+    pub fn publish(&mut self, world: &World) {
+        if self.sinks.is_empty() || world.events.is_empty() {
+            return;
+        }
+        let events = world
+            .events
+            .iter()
+            .filter(|event| !matches!(event, WorldEvent::Thrust { .. }));
+        for event in events {
+            let record = EventRecord {
+                session: self.session,
+                game: self.game,
+                tick: world.tick,
+                event,
+            };
+            self.sinks.iter_mut().for_each(|sink| sink.record(&record));
+        }
+        self.sinks.iter_mut().for_each(|sink| sink.flush());
+    }
+}
+
+/// The JsonLinesSink struct is the event log: it appends every event to a file as a line of JSON, such as
+///
+/// ```text
+/// {"session":1792368000,"game":1,"tick":412,"event":"Destroyed","ship":0,"cause":"Asteroid","x":38.5,"y":41.2}
+/// ```
+///
+/// A file that can't be written to is reported once on stderr, the following events are dropped.
+pub struct JsonLinesSink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    failed: bool,
+}
+
+impl JsonLinesSink {
+    /// The create function opens the event log at the given path, the events are appended to the file if it exists.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesSink {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            failed: false,
+        })
+    }
+
+    /// The report function reports the first error met writing the event log.
+    fn report(&mut self, error: impl std::fmt::Display) {
+        if !self.failed {
+            eprintln!(
+                "Could not write the events to {}: {}",
+                self.path.display(),
+                error
+            );
+            self.failed = true;
+        }
+    }
+}
+
+impl EventSink for JsonLinesSink {
+    fn record(&mut self, record: &EventRecord) {
+        if self.failed {
+            return;
+        }
+        let result = serde_json::to_writer(&mut self.writer, record)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(self.writer));
+        if let Err(error) = result {
+            self.report(error);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Err(error) = self.writer.flush() {
+            self.report(error);
+        }
+    }
+}
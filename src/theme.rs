@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The directory holding the palette files, relative to where the game is started.
+pub const THEMES_DIRECTORY: &str = "themes";
+
+/// The name of the theme used when none was chosen, or when the chosen one can't be found.
+pub const DEFAULT_THEME: &str = "Classic";
+
+/// A color, as red, green and blue components, the same as bracket-lib's named colors.
+pub type Color = (u8, u8, u8);
+
+/// The Theme struct is how the game looks: the color of everything drawn in the arena, and whether things move only
+/// for show. Palette files in the themes directory are RON files of this struct, every color they leave out is the
+/// classic one, so a palette only needs to list what it changes. For example:
+///
+/// ```ron
+/// (
+///     name: "Amber",
+///     ship: (255, 191, 0),
+///     asteroid: (255, 255, 255),
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    /// The title field is the color of the screens' titles, the game's title stands on title_background.
+    pub title: Color,
+    pub title_background: Color,
+    pub highlight: Color,
+    /// The positive field is the color of what went well, such as the unlocked achievements or a level saved, disabled
+    /// the one of what can't be chosen or isn't reached yet, such as the locked achievements.
+    pub positive: Color,
+    pub disabled: Color,
+    pub ship: Color,
+    pub shield: Color,
+    /// The ghost field is the color of the raced ghost, it is drawn translucent.
+    pub ghost: Color,
+    pub asteroid: Color,
+    pub projectile: Color,
+    pub pad: Color,
+    /// The pad_lit field is the color of the pad to reach in a pad sequence, pad_reached the one of the pads already
+    /// reached.
+    pub pad_lit: Color,
+    pub pad_reached: Color,
+    pub terrain: Color,
+    pub wind: Color,
+    pub planetoid: Color,
+    pub black_hole: Color,
+    pub pickup_shield: Color,
+    pub pickup_slow_time: Color,
+    pub pickup_shockwave: Color,
+    /// The reduced_motion field stops the asteroids from spinning and the screens from fading in. It is not part of the
+    /// palette files, it is set from the options.
+    #[serde(skip)]
+    pub reduced_motion: bool,
+}
+
+impl Default for Theme {
+    /// The default theme is the classic one, with the game's original colors.
+    fn default() -> Self {
+        Theme {
+            name: DEFAULT_THEME.to_string(),
+            title: (255, 165, 0),
+            title_background: (200, 0, 0),
+            highlight: (255, 255, 0),
+            positive: (0, 255, 0),
+            disabled: (128, 128, 128),
+            ship: (0, 0, 255),
+            shield: (0, 255, 255),
+            ghost: (120, 160, 255),
+            asteroid: (255, 0, 0),
+            projectile: (255, 255, 0),
+            pad: (0, 255, 0),
+            pad_lit: (255, 255, 0),
+            pad_reached: (169, 169, 169),
+            terrain: (139, 69, 19),
+            wind: (173, 216, 230),
+            planetoid: (139, 69, 19),
+            black_hole: (128, 0, 128),
+            pickup_shield: (0, 255, 255),
+            pickup_slow_time: (255, 0, 255),
+            pickup_shockwave: (255, 255, 255),
+            reduced_motion: false,
+        }
+    }
+}
+
+impl Theme {
+    /// The builtin function returns the themes shipped with the game. Besides the classic colors, the deuteranopia
+    /// and protanopia themes never tell things apart by red and green alone, they use the blues and oranges of the
+    /// Okabe-Ito palette instead, and the high contrast theme uses pure colors on the black background.
+    pub fn builtin() -> Vec<Theme> {
+        let classic = Theme::default();
+        let deuteranopia = Theme {
+            name: "Deuteranopia".to_string(),
+            title: (230, 159, 0),
+            title_background: (0, 114, 178),
+            positive: (86, 180, 233),
+            disabled: (120, 120, 120),
+            ship: (86, 180, 233),
+            shield: (86, 180, 233),
+            ghost: (204, 121, 167),
+            asteroid: (213, 94, 0),
+            projectile: (240, 228, 66),
+            pad: (0, 114, 178),
+            pad_lit: (240, 228, 66),
+            pad_reached: (120, 120, 120),
+            terrain: (153, 102, 51),
+            pickup_shield: (86, 180, 233),
+            pickup_slow_time: (204, 121, 167),
+            ..classic.clone()
+        };
+        // Protanopes see the same blues and oranges, but reds look dark to them: the asteroids are a bright orange
+        // rather than a vermilion, and the yellows and browns are swapped for white and grey
+        let protanopia = Theme {
+            name: "Protanopia".to_string(),
+            asteroid: (230, 159, 0),
+            projectile: (255, 255, 255),
+            pad_lit: (255, 255, 255),
+            terrain: (110, 110, 110),
+            planetoid: (110, 110, 110),
+            ..deuteranopia.clone()
+        };
+        let high_contrast = Theme {
+            name: "High contrast".to_string(),
+            title: (255, 255, 255),
+            title_background: (0, 0, 0),
+            positive: (0, 255, 255),
+            disabled: (160, 160, 160),
+            ship: (255, 255, 255),
+            shield: (0, 255, 255),
+            ghost: (255, 255, 255),
+            asteroid: (255, 255, 0),
+            projectile: (255, 255, 255),
+            pad: (0, 255, 255),
+            pad_lit: (255, 0, 255),
+            pad_reached: (128, 128, 128),
+            terrain: (211, 211, 211),
+            wind: (255, 255, 255),
+            planetoid: (211, 211, 211),
+            black_hole: (255, 0, 255),
+            ..classic.clone()
+        };
+        vec![classic, deuteranopia, protanopia, high_contrast]
+    }
+
+    /// The registry function returns every theme that can be chosen: the builtin ones, then the palette files of the
+    /// themes directory in alphabetical order. A palette file that can't be read is reported on stderr and skipped.
+    pub fn registry() -> Vec<Theme> {
+        let mut themes = Theme::builtin();
+        let Ok(entries) = fs::read_dir(THEMES_DIRECTORY) else {
+            return themes;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match Theme::load(&path) {
+                Ok(theme) => themes.push(theme),
+                Err(error) => eprintln!("Ignoring {}", error),
+            }
+        }
+        themes
+    }
+
+    /// The load function reads a palette file, a palette without a name is named after its file.
+    pub fn load(path: &Path) -> Result<Theme, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let mut theme: Theme = ron::from_str(&text).map_err(|error| format!("{}:{}", path.display(), error))?;
+        if theme.name == DEFAULT_THEME {
+            if let Some(stem) = path.file_stem() {
+                theme.name = stem.to_string_lossy().to_string();
+            }
+        }
+        Ok(theme)
+    }
+
+    /// The dimmed function returns the theme with every color darkened, to draw the frozen game under the pause menu.
+    pub fn dimmed(&self) -> Theme {
+        let dim = |(red, green, blue): Color| (red / 3, green / 3, blue / 3);
+        Theme {
+            name: self.name.clone(),
+            title: dim(self.title),
+            title_background: dim(self.title_background),
+            highlight: dim(self.highlight),
+            positive: dim(self.positive),
+            disabled: dim(self.disabled),
+            ship: dim(self.ship),
+            shield: dim(self.shield),
+            ghost: dim(self.ghost),
+            asteroid: dim(self.asteroid),
+            projectile: dim(self.projectile),
+            pad: dim(self.pad),
+            pad_lit: dim(self.pad_lit),
+            pad_reached: dim(self.pad_reached),
+            terrain: dim(self.terrain),
+            wind: dim(self.wind),
+            planetoid: dim(self.planetoid),
+            black_hole: dim(self.black_hole),
+            pickup_shield: dim(self.pickup_shield),
+            pickup_slow_time: dim(self.pickup_slow_time),
+            pickup_shockwave: dim(self.pickup_shockwave),
+            // The frozen asteroids don't spin
+            reduced_motion: true,
+        }
+    }
+
+    /// The find function returns the theme of the registry with the given name, or the classic theme if there is
+    /// none.
+    pub fn find(name: &str) -> Theme {
+        Theme::registry()
+            .into_iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,130 @@
+use crate::level::{Level, PadDefinition, WinCondition};
+use crate::map::PadMotion;
+use crate::storage;
+use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The name of the time attack records' save file, in the data directory.
+const SAVE_FILE: &str = "time_attack.json";
+
+/// The version of the time attack records' save file written by this version of the game.
+const SAVE_VERSION: u32 = 1;
+
+/// The number of pads in a time attack course.
+pub const COURSE_PADS: usize = 5;
+
+/// The number of ticks the simulation runs per second, used to show the times in seconds.
+const TICKS_PER_SECOND: f32 = 60.0;
+
+/// The course function generates the time attack course of the given seed: pads of random sizes spread across the
+/// arena, to be landed on in order, without asteroids to get in the way. The same seed always gives the same course.
+pub fn course(seed: u64) -> Level {
+    let mut random = RandomNumberGenerator::seeded(seed);
+    let level = Level::default();
+    let (width, height) = (level.arena.width, level.arena.height);
+
+    // Each pad gets a slice of the arena, the slices are then shuffled so that the course zigzags
+    let slice = width / COURSE_PADS as i32;
+    let mut slices: Vec<i32> = (0..COURSE_PADS as i32).collect();
+    for index in (1..slices.len()).rev() {
+        let other = random.range(0, index as i32 + 1) as usize;
+        slices.swap(index, other);
+    }
+    let pads = slices
+        .iter()
+        .map(|slice_index| {
+            let width = random.range(1, 4) * 2 + 1;
+            PadDefinition {
+                x: slice_index * slice + random.range(width / 2 + 1, slice - width / 2 - 1),
+                heigth: random.range(height / 8, height / 2),
+                width,
+                motion: PadMotion::Static,
+            }
+        })
+        .collect();
+
+    Level {
+        name: "Time attack".to_string(),
+        pads,
+        random_asteroids: false,
+        pickups: false,
+        win: WinCondition::PadSequence,
+        ..level
+    }
+}
+
+/// The format_time function shows a number of ticks as seconds, with hundredths.
+pub fn format_time(ticks: u64) -> String {
+    format!("{:.2}s", ticks as f32 / TICKS_PER_SECOND)
+}
+
+/// The BestTime struct is the fastest run of a course: the tick at which each pad was reached, the last one being the
+/// total time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BestTime {
+    pub splits: Vec<u64>,
+}
+
+impl BestTime {
+    /// The total function returns the time of the whole run, in ticks.
+    pub fn total(&self) -> u64 {
+        self.splits.last().copied().unwrap_or(u64::MAX)
+    }
+}
+
+/// The Records struct keeps the best time of every course played, by seed. It is saved in the data directory.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Records {
+    pub version: u32,
+    pub best: BTreeMap<u64, BestTime>,
+}
+
+impl Default for Records {
+    fn default() -> Self {
+        Records {
+            version: SAVE_VERSION,
+            best: BTreeMap::new(),
+        }
+    }
+}
+
+impl Records {
+    /// The load function reads the records from the data directory. A file written by a newer version of the game is
+    /// ignored rather than misread.
+    pub fn load() -> Self {
+        let records: Records = storage::load(SAVE_FILE);
+        if records.version > SAVE_VERSION {
+            eprintln!(
+                "Ignoring {}: version {} is not supported",
+                SAVE_FILE, records.version
+            );
+            return Records::default();
+        }
+        records
+    }
+
+    /// The save function writes the records to the data directory.
+    pub fn save(&self) {
+        if let Err(error) = storage::save(SAVE_FILE, self) {
+            eprintln!("Could not save the time attack records: {}", error);
+        }
+    }
+
+    /// The record function keeps a finished run of the course of the given seed if it is its best time, and returns
+    /// true if it is.
+    pub fn record(&mut self, seed: u64, splits: &[u64]) -> bool {
+        let run = BestTime {
+            splits: splits.to_vec(),
+        };
+        let faster = self
+            .best
+            .get(&seed)
+            .is_none_or(|best| run.total() < best.total());
+        if faster {
+            self.best.insert(seed, run);
+        }
+        faster
+    }
+}
@@ -0,0 +1,99 @@
+use crate::character::Character;
+use crate::hazards::Asteroid;
+use crate::theme::Theme;
+use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The number of ticks a ship waits between two shots.
+pub const FIRE_COOLDOWN: u32 = 12;
+
+/// The distance a projectile travels during a tick.
+pub const PROJECTILE_SPEED: f32 = 1.2;
+
+/// The number of ticks a projectile flies before fading out.
+pub const PROJECTILE_RANGE: u32 = 40;
+
+/// The smallest size an asteroid can fragment into, smaller asteroids are destroyed without fragments.
+pub const MIN_FRAGMENT_SIZE: f32 = 1.0;
+
+/// The angle, in degrees, between a fragmenting asteroid's course and the course of each of its two fragments.
+const FRAGMENT_SPREAD: f32 = 30.0;
+
+/// The Projectile struct is a shot fired by a ship, it travels straight up from where the ship was when it fired.
+/// It only holds plain values so that it can be sent as it is in the multiplayer snapshots.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Projectile {
+    pub x: f32,
+    pub y: f32,
+    /// The ship field is the index of the ship which fired the projectile, it is credited with the kills.
+    pub ship: usize,
+    /// The range field is the number of ticks the projectile still flies.
+    pub range: u32,
+}
+
+impl Projectile {
+    /// The new function is used to fire a projectile from the character's position.
+    pub fn new(character: &Character, ship: usize) -> Self {
+        Projectile {
+            x: character.coordinate.x,
+            y: character.coordinate.y - 1.0,
+            ship,
+            range: PROJECTILE_RANGE,
+        }
+    }
+
+    /// The update function moves the projectile by one tick, it returns false once the projectile faded out or left
+    /// the top of the arena.
+    pub fn update(&mut self) -> bool {
+        self.y -= PROJECTILE_SPEED;
+        self.range = self.range.saturating_sub(1);
+        self.range > 0 && self.y >= 0.0
+    }
+
+    /// The check_hit function is used to check if the projectile hit the asteroid, with the same distance as a ship's
+    /// collision.
+    pub fn check_hit(&self, asteroid: &Asteroid) -> bool {
+        let x = asteroid.coordinate.x - self.x;
+        let y = asteroid.coordinate.y - self.y;
+        (x * x + y * y).sqrt() < asteroid.scaling.x / 2.0
+    }
+
+    /// The render function is used to draw the projectile on the fancy-console (id 1), then shift back to the main
+    /// console (id 0).
+    pub fn render(&self, ctx: &mut BTerm, theme: &Theme) {
+        ctx.set_active_console(1);
+        ctx.set_fancy(
+            PointF::new(self.x, self.y),
+            1,
+            Degrees::new(0.0),
+            PointF::new(1.0, 1.0),
+            theme.projectile,
+            BLACK,
+            to_cp437('|'),
+        );
+        ctx.set_active_console(0);
+    }
+}
+
+/// The fragment function splits a destroyed asteroid into two asteroids of half its size, flying apart on each side
+/// of its course. An asteroid too small to split leaves no fragment. Fragments fall only once, like the asteroids of a
+/// level.
+pub fn fragment(asteroid: &Asteroid) -> Vec<Asteroid> {
+    let size = asteroid.scaling.x / 2.0;
+    if size < MIN_FRAGMENT_SIZE {
+        return Vec::new();
+    }
+    let heading = asteroid.trajectory.to_degrees();
+    [-FRAGMENT_SPREAD, FRAGMENT_SPREAD]
+        .iter()
+        .map(|spread| {
+            Asteroid::scripted(
+                asteroid.coordinate.x,
+                asteroid.coordinate.y,
+                heading + spread,
+                asteroid.speed * 1.2,
+                size,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,1053 @@
+use crate::character::{Character, ShipInput};
+use crate::environment::{self, Environment, GravityWell, Vector, WellKind};
+use crate::hazards::{Asteroid, Pickup, PickupKind};
+use crate::i18n::Catalog;
+use crate::level::{Level, SpawnDefinition, WinCondition};
+use crate::map::{LandingPad, Terrain};
+use crate::theme::Theme;
+use crate::weapon::{self, Projectile, FIRE_COOLDOWN};
+use bracket_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// The Arena struct holds the parts of the world that don't move on their own: its size, the forces applied to the
+/// ships, the ground and the landing pads. It is everything a ship needs to know to fly.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Arena {
+    pub width: i32,
+    pub height: i32,
+    pub environment: Environment,
+    /// The wind field is the speed of the wind during the current tick, see Wind::at.
+    pub wind: f32,
+    /// The wells field lists the gravity wells pulling the ships, the asteroids and the pickups.
+    pub wells: Vec<GravityWell>,
+    pub terrain: Terrain,
+    pub landing_pads: Vec<LandingPad>,
+}
+
+impl Arena {
+    /// The fly function applies one frame of input and physics to a single ship: thrust, the pull of the gravity
+    /// wells, the environment's gravity and drag, then momentum, before keeping the ship inside the arena, above the ground and on top of the landing pads.
+    /// It returns the index of the landing pad the ship is resting on, if any.
+    pub fn fly(&self, character: &mut Character, input: &ShipInput) -> Option<usize> {
+        character.apply_input(input);
+
+        // Accumulate the forces applied to the character's ship, then apply momentum to the ship
+        character.apply_force(self.pull(character.coordinate.x, character.coordinate.y));
+        character.apply_environment(&self.environment, self.wind);
+        character.apply_momentum();
+
+        // Keep the character's ship inside the arena and above the ground
+        character.coordinate.x = World::keep_in_bounds(character.coordinate.x, 0, self.width - 1);
+        character.coordinate.y = World::keep_in_bounds(
+            character.coordinate.y,
+            1,
+            self.height - self.terrain.height_at(character.coordinate.x as i32),
+        );
+
+        // Check if the character's ship has landed on a landing pad, a ship too fast for a moving pad skids on it
+        let contact = self
+            .landing_pads
+            .iter()
+            .position(|landing_pad| landing_pad.check_landing(character));
+        let landed = contact.filter(|index| character.landed || self.landing_pads[*index].accepts(character));
+        if let Some(index) = contact {
+            character.coordinate.y = (self.height - self.landing_pads[index].heigth) as f32;
+        }
+        character.landed = landed.is_some();
+        landed
+    }
+
+    /// The impact function is used when an asteroid of the given size crashes at the given position: a pad it hit is
+    /// chipped, otherwise a crater is dug in the ground, whose debris never pile up higher than half the arena.
+    pub fn impact(&mut self, x: f32, y: f32, size: f32) {
+        if let Some(landing_pad) = self
+            .landing_pads
+            .iter_mut()
+            .find(|landing_pad| landing_pad.covers(x, y))
+        {
+            landing_pad.chip();
+            return;
+        }
+        let radius = (size / 2.0).round() as i32;
+        let depth = (size / 2.0).ceil() as i32;
+        self.terrain
+            .carve(x.round() as i32, radius, depth, self.width, self.height / 2);
+    }
+
+    /// The pull function returns the sum of the pulls of every gravity well on a body at the given position.
+    pub fn pull(&self, x: f32, y: f32) -> Vector {
+        let mut force = Vector::default();
+        for well in self.wells.iter() {
+            force += well.force_at(x, y);
+        }
+        force
+    }
+
+    /// The well_at function returns the gravity well the given position is inside of, if any.
+    pub fn well_at(&self, x: f32, y: f32) -> Option<&GravityWell> {
+        self.wells.iter().find(|well| well.contains(x, y))
+    }
+
+    /// The ground function returns the vertical coordinate of the ground's surface at the given column.
+    pub fn ground(&self, x: f32) -> f32 {
+        (self.height - self.terrain.height_at(x as i32)) as f32
+    }
+}
+
+/// The distance around an asteroid's collision radius in which a ship passing by counts as a near miss.
+pub const NEAR_MISS_MARGIN: f32 = 1.5;
+
+/// The points a near miss is worth, before the combo multiplier is applied.
+pub const NEAR_MISS_POINTS: usize = 1;
+
+/// The highest the combo multiplier goes.
+pub const MAX_COMBO: usize = 5;
+
+/// The number of ticks the combo multiplier holds after a near miss before it drops by one, about 3 seconds.
+pub const COMBO_TICKS: u64 = 180;
+
+/// The chance of a pickup starting to fall during a tick is one in PICKUP_CHANCE, about one every 15 seconds.
+pub const PICKUP_CHANCE: i32 = 900;
+
+/// The number of ticks a shield lasts once collected, unless an asteroid breaks it first.
+pub const SHIELD_DURATION: u64 = 600;
+
+/// The number of ticks time stays slowed down once a slow-time pickup is collected.
+pub const SLOW_TIME_DURATION: u64 = 300;
+
+/// The factor applied to the speed of the asteroids and pickups while time is slowed down.
+pub const SLOW_TIME_SCALE: f32 = 0.4;
+
+/// The distance from the ship under which a shockwave clears the asteroids.
+pub const SHOCKWAVE_RADIUS: f32 = 12.0;
+
+/// The number of ticks a ship must rest on the lit pad of a pad sequence for it to count.
+pub const SETTLE_TICKS: u64 = 30;
+
+/// The WorldEvent enum lists what happened during a step of the simulation, so that the game can react to it
+/// (achievements, statistics, the event log...) without looking into the simulation itself. Ships are given by their
+/// index, asteroids by their id, and positions are where the ship or the asteroid was at the time.
+///
+/// # Variants
+/// - Spawned: An asteroid entered the world, falling from its position
+/// - Thrust: A ship fired at least one of its thrusters during the tick, it is not published to the event log
+/// - ThrustStarted, ThrustStopped: A ship started firing its thrusters, or stopped firing all of them
+/// - Landed: A ship touched down on a landing pad, with its vertical speed at the time
+/// - Avoided: An asteroid left the arena or hit the ground
+/// - Scored: The score increased, with the progress made, see World::progress
+/// - NearMiss: An asteroid passed close to a ship without hitting it, with the points it scored and the combo
+///   multiplier it raised
+/// - Collided: An asteroid hit a ship, it is followed by ShieldBroken or Destroyed
+/// - Destroyed: A ship was destroyed, see DeathCause
+/// - PickedUp: A ship collected a pickup
+/// - ShieldBroken: A ship's shield absorbed an asteroid, the ship survived but lost its shield
+/// - Fired: A ship fired a projectile
+/// - Killed: A ship's projectile destroyed an asteroid
+/// - PadReached: A ship rested long enough on the lit pad of a pad sequence
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event")]
+pub enum WorldEvent {
+    Spawned { asteroid: u64, x: f32, y: f32 },
+    Thrust { ship: usize, input: ShipInput },
+    ThrustStarted { ship: usize, x: f32, y: f32 },
+    ThrustStopped { ship: usize, x: f32, y: f32 },
+    Landed { ship: usize, pad: usize, vertical_speed: f32, x: f32, y: f32 },
+    Avoided { asteroid: u64, x: f32, y: f32 },
+    Scored { score: usize, progress: usize },
+    NearMiss { ship: usize, asteroid: u64, x: f32, y: f32, points: usize, combo: usize },
+    Collided { ship: usize, asteroid: u64, x: f32, y: f32 },
+    Destroyed { ship: usize, cause: DeathCause, x: f32, y: f32 },
+    PickedUp { ship: usize, kind: PickupKind },
+    ShieldBroken { ship: usize },
+    Fired { ship: usize },
+    Killed { ship: usize },
+    PadReached { ship: usize, pad: usize },
+}
+
+/// The DeathCause enum lists what can destroy a ship, it is part of the statistics' save file.
+///
+/// # Variants
+/// - Asteroid: The ship collided with an asteroid
+/// - Planetoid: The ship crashed into a planetoid
+/// - BlackHole: The ship was swallowed by a black hole
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DeathCause {
+    Asteroid,
+    Planetoid,
+    BlackHole,
+}
+
+/// The World struct holds the simulation of a game: the ships, the asteroids, the arena they fly in and the score.
+/// It does not read the keyboard, so the same simulation can run inside the game window and in the headless
+/// multiplayer server.
+pub struct World {
+    /// The characters field stores every ship taking part in the game, a local game only has one.
+    pub characters: Vec<Character>,
+
+    /// The asteroids vector, this is used to store the asteroids' position and trajectory
+    pub asteroids: Vec<Asteroid>,
+
+    /// The arena field stores the size of the world, its physics, its ground and its landing pads
+    pub arena: Arena,
+
+    /// The pickups field stores the power-ups currently falling
+    pub pickups: Vec<Pickup>,
+
+    /// The pickups_enabled field is set when pickups fall from time to time
+    pub pickups_enabled: bool,
+
+    /// The slow_time field is the number of ticks time stays slowed down, time runs normally while it is 0
+    pub slow_time: u64,
+
+    /// The weapons field is set when the ships can shoot, as in the arcade blaster mode
+    pub weapons: bool,
+
+    /// The projectiles field stores the shots fired by the ships which are still flying
+    pub projectiles: Vec<Projectile>,
+
+    /// The spawns field lists the asteroids the level will throw, sorted by tick, they are removed once thrown
+    pub spawns: Vec<SpawnDefinition>,
+
+    /// The random_asteroids field is set when random asteroids fall, as in the endless game
+    pub random_asteroids: bool,
+
+    /// The craters field is set when the asteroids crashing down dig craters and chip the pads, see Arena::impact
+    pub craters: bool,
+
+    /// The win field tells when the game is won
+    pub win: WinCondition,
+
+    /// The visited field stores, for each landing pad, whether a ship already landed on it
+    pub visited: Vec<bool>,
+
+
+    /// The score, this is used to keep track of the players' score
+    pub score: usize,
+
+    /// The avoided, this is used to keep track of the number of asteroids avoided, which in turn is used to
+    /// update score once enough asteroids were avoided
+    pub avoided: usize,
+
+    /// The progress field counts the points of the asteroids avoided and destroyed, without the combo multiplier nor
+    /// the near misses. It grows the way the score did before near misses were scored, so the score can be inflated by
+    /// risky flying without sending more random asteroids or unlocking the score achievements sooner
+    pub progress: usize,
+
+    /// The tick field counts the number of simulation steps since the world was created.
+    pub tick: u64,
+
+    /// The next_id field is the last id given to an asteroid, see World::identify.
+    pub next_id: u64,
+
+    /// The random field is the generator used for every random decision of the simulation, a world created with
+    /// World::seeded always plays out the same way for the same inputs.
+    pub random: RandomNumberGenerator,
+
+    /// The events field lists what happened since the last call to step, it is cleared at the start of each step.
+    pub events: Vec<WorldEvent>,
+}
+
+impl World {
+    /// The new function is used to create a new world with the given number of ships, a single asteroid at the top
+    /// of the screen and a randomly placed landing pad.
+    pub fn new(players: usize) -> Self {
+        World::from_level(&Level::default(), players, RandomNumberGenerator::new())
+    }
+
+    /// The seeded function creates a new world like the new function, but the asteroids and the landing pad are
+    /// generated from the given seed.
+    pub fn seeded(players: usize, seed: u64) -> Self {
+        World::from_level(
+            &Level::default(),
+            players,
+            RandomNumberGenerator::seeded(seed),
+        )
+    }
+
+    /// The from_level function creates a new world from a level. A level without landing pads gets a randomly
+    /// placed one, and a level with random asteroids starts with one of them at the top of the screen.
+    pub fn from_level(level: &Level, players: usize, mut random: RandomNumberGenerator) -> Self {
+        let (width, height) = (level.arena.width, level.arena.height);
+
+        let mut asteroids = Vec::new();
+        if level.random_asteroids {
+            let starting_point = random.range(0.0, width as f32);
+            asteroids.push(Asteroid::new(starting_point, 0.0, &mut random));
+        }
+
+        let landing_pads = if level.pads.is_empty() {
+            vec![LandingPad::new(random.range(0, width), height, &mut random)]
+        } else {
+            level
+                .pads
+                .iter()
+                .map(|pad| LandingPad::placed(pad.x, height, pad.heigth, pad.width, pad.motion))
+                .collect()
+        };
+
+        let mut spawns = level.spawns.clone();
+        spawns.sort_by_key(|spawn| spawn.tick);
+
+        World {
+            characters: (0..players)
+                .map(|_| World::spawn_character(width, height))
+                .collect(),
+            asteroids,
+            visited: vec![false; landing_pads.len()],
+            arena: Arena {
+                width,
+                height,
+                environment: level.environment(),
+                wind: level.environment().wind.at(0),
+                wells: level.wells.clone(),
+                terrain: Terrain {
+                    heights: level.terrain.clone(),
+                },
+                landing_pads,
+            },
+            pickups: Vec::new(),
+            pickups_enabled: level.pickups,
+            slow_time: 0,
+            weapons: level.weapons,
+            projectiles: Vec::new(),
+            spawns,
+            random_asteroids: level.random_asteroids,
+            craters: level.craters,
+            win: level.win,
+            score: 0,
+            avoided: 0,
+            progress: 0,
+            tick: 0,
+            next_id: 0,
+            random,
+            events: Vec::new(),
+        }
+    }
+
+    /// The spawn_character function creates a ship at the starting position, at the bottom middle of the arena.
+    pub fn spawn_character(width: i32, height: i32) -> Character {
+        Character::new((width / 2) as f32, height as f32)
+    }
+
+    /// The step function advances the whole simulation by one frame. Each ship is flown with the input at the same
+    /// index in the inputs slice, ships without a matching input don't fire their thrusters.
+    pub fn step(&mut self, inputs: &[ShipInput]) {
+        self.events.clear();
+        for index in 0..self.characters.len() {
+            let input = inputs.get(index).copied().unwrap_or_default();
+            self.fly(index, &input);
+        }
+        self.advance();
+    }
+
+    /// The fly function flies the ship at the given index for one frame, see Arena::fly, fires its weapon and keeps
+    /// track of the landing pads it landed on.
+    pub fn fly(&mut self, index: usize, input: &ShipInput) {
+        let Some(character) = self.characters.get_mut(index) else {
+            return;
+        };
+        if character.destroyed {
+            return;
+        }
+        let thrusting = input.up || input.left || input.right;
+        if thrusting {
+            self.events.push(WorldEvent::Thrust {
+                ship: index,
+                input: *input,
+            });
+        }
+        if thrusting != character.thrusting {
+            character.thrusting = thrusting;
+            let (x, y) = (character.coordinate.x, character.coordinate.y);
+            self.events.push(if thrusting {
+                WorldEvent::ThrustStarted { ship: index, x, y }
+            } else {
+                WorldEvent::ThrustStopped { ship: index, x, y }
+            });
+        }
+
+        character.cooldown = character.cooldown.saturating_sub(1);
+        if self.weapons && input.fire && character.cooldown == 0 {
+            character.cooldown = FIRE_COOLDOWN;
+            self.projectiles.push(Projectile::new(character, index));
+            self.events.push(WorldEvent::Fired { ship: index });
+        }
+
+        let was_landed = character.landed;
+        let vertical_speed = character.momentum.vertical;
+        let landed = self.arena.fly(character, input);
+
+        // In a pad sequence, a ship reaches its target pad once it rested on it long enough, it then aims for the next one
+        if self.win == WinCondition::PadSequence {
+            if landed.is_some() && landed == Some(character.target) {
+                character.contact += 1;
+                if character.contact >= SETTLE_TICKS {
+                    // The tick being flown counts, so a split is the number of ticks elapsed
+                    character.splits.push(self.tick + 1);
+                    self.events.push(WorldEvent::PadReached {
+                        ship: index,
+                        pad: character.target,
+                    });
+                    character.target += 1;
+                    character.contact = 0;
+                }
+            } else {
+                character.contact = 0;
+            }
+        }
+
+        let Some(pad) = landed else {
+            return;
+        };
+        if let Some(visited) = self.visited.get_mut(pad) {
+            *visited = true;
+        }
+        if !was_landed {
+            self.events.push(WorldEvent::Landed {
+                ship: index,
+                pad,
+                vertical_speed,
+                x: character.coordinate.x,
+                y: character.coordinate.y,
+            });
+        }
+    }
+
+    /// The advance function moves everything but the ships by one frame: it moves the pads and the ships resting on
+    /// them, throws the level's asteroids, updates the asteroids and the pickups pulled by the gravity wells, spawns
+    /// new ones as the score grows, counts the avoided asteroids, hands out the pickups collected and destroys the ships
+    /// that collided with an asteroid or fell into a gravity well.
+    pub fn advance(&mut self) {
+        // Move the pads to where they are on the next tick, carrying along the ships resting on them
+        let next = self.tick + 1;
+        for landing_pad in self.arena.landing_pads.iter_mut() {
+            if !landing_pad.is_moving() {
+                continue;
+            }
+            let before = landing_pad.clone();
+            landing_pad.update(next);
+            let shift_x = (landing_pad.x - before.x) as f32;
+            let shift_y = (landing_pad.heigth - before.heigth) as f32;
+            for character in self.characters.iter_mut() {
+                if character.landed && !character.destroyed && before.check_landing(character) {
+                    character.coordinate.x += shift_x;
+                    character.coordinate.y -= shift_y;
+                }
+            }
+        }
+
+        // Throw the level's asteroids which are due
+        while self.spawns.first().is_some_and(|spawn| spawn.tick <= self.tick) {
+            let spawn = self.spawns.remove(0);
+            self.asteroids.push(Asteroid::scripted(
+                spawn.x,
+                spawn.y,
+                spawn.heading,
+                spawn.speed,
+                spawn.size,
+            ));
+        }
+
+        // Update the asteroids' and the pickups' position, slower while time is slowed down, after bending their course
+        // towards the gravity wells
+        let time_scale = if self.slow_time > 0 { SLOW_TIME_SCALE } else { 1.0 };
+        self.slow_time = self.slow_time.saturating_sub(1);
+        if !self.arena.wells.is_empty() {
+            let arena = &self.arena;
+            let bodies = self
+                .asteroids
+                .iter_mut()
+                .chain(self.pickups.iter_mut().map(|pickup| &mut pickup.body));
+            for body in bodies {
+                let force = arena.pull(body.coordinate.x, body.coordinate.y);
+                body.apply_force(force, time_scale);
+            }
+        }
+        self.asteroids
+            .iter_mut()
+            .for_each(|asteroid| asteroid.update(time_scale));
+        self.pickups
+            .iter_mut()
+            .for_each(|pickup| pickup.body.update(time_scale));
+
+        // Once in a while, a pickup starts falling from the top of the screen, the pickups which left the arena are lost
+        if self.pickups_enabled && self.random.range(0, PICKUP_CHANCE) == 0 {
+            let starting_point = self.random.range(1.0, self.arena.width as f32 - 1.0);
+            self.pickups
+                .push(Pickup::new(starting_point, 0.0, &mut self.random));
+        }
+        let arena = &self.arena;
+        self.pickups.retain_mut(|pickup| {
+            let ground = arena.ground(pickup.body.coordinate.x) as i32;
+            let position = pickup.body.coordinate;
+            !pickup.body.check_finished_course(arena.width, ground) && arena.well_at(position.x, position.y).is_none()
+        });
+
+        self.shoot();
+
+        // Check if the number of asteroid is less than the score, if so, add a new asteroid to the game, this is the project's
+        // way of increasing the difficulty as the player's score increases
+        let random_asteroids = self.asteroids.iter().filter(|asteroid| !asteroid.scripted).count();
+        if self.random_asteroids && random_asteroids < self.progress {
+            let starting_point = self.random.range(1.0, self.arena.width as f32 - 1.0);
+            self.asteroids
+                .push(Asteroid::new(starting_point, 0.0, &mut self.random));
+        }
+
+        // Check for asteroid which left the arena, hit the ground or a pad, or fell into a gravity well and increase the avoided counter, if the
+        // avoided counter is greater than the number of asteroid, increase the score and reset the avoided counter. Random asteroids are sent
+        // back to the top, the level's asteroids are removed. The asteroids which crashed down are kept track of, to dig their craters, and
+        // the ships they were passing close to when they finished their course were grazed.
+        let arena = &self.arena;
+        let random = &mut self.random;
+        let events = &mut self.events;
+        let craters = self.craters;
+        let mut avoided = 0;
+        let mut impacts = Vec::new();
+        let mut grazes = Vec::new();
+        self.asteroids.retain_mut(|asteroid| {
+            let (x, y) = (asteroid.coordinate.x, asteroid.coordinate.y);
+            let ground = arena.ground(x) as i32;
+            let swallowed = arena.well_at(x, y).is_some();
+            let on_pad = craters && arena.landing_pads.iter().any(|landing_pad| landing_pad.covers(x, y));
+            if !swallowed && !on_pad && !asteroid.check_finished_course(arena.width, ground) {
+                return true;
+            }
+            let crashed = on_pad || (y > ground as f32 && (0.0..arena.width as f32).contains(&x));
+            if craters && !swallowed && crashed {
+                impacts.push((x, y, asteroid.scaling.x));
+            }
+            avoided += 1;
+            grazes.extend(asteroid.grazing.iter().map(|ship| (*ship, asteroid.id, x, y)));
+            events.push(WorldEvent::Avoided {
+                asteroid: asteroid.id,
+                x,
+                y,
+            });
+            if asteroid.scripted {
+                return false;
+            }
+            let starting_point = random.range(1.0, arena.width as f32 - 1.0);
+            *asteroid = Asteroid::new(starting_point, 0.0, random);
+            true
+        });
+        for (x, y, size) in impacts {
+            self.arena.impact(x, y, size);
+        }
+        self.identify();
+        self.avoided += avoided;
+        if self.avoided > self.asteroids.len() {
+            self.avoided = 0;
+            // The point is shared by every ship, it is multiplied by the best combo among the ones still flying
+            let combo = self
+                .characters
+                .iter()
+                .filter(|character| !character.destroyed)
+                .map(|character| character.combo)
+                .max()
+                .unwrap_or(1);
+            self.progress += 1;
+            self.add_score(1, combo);
+        }
+
+        // Hand out the pickups caught by the ships
+        let mut collected = Vec::new();
+        for (ship, character) in self.characters.iter_mut().enumerate() {
+            if character.destroyed {
+                continue;
+            }
+            self.pickups.retain_mut(|pickup| {
+                if pickup.check_collected(character) {
+                    collected.push((ship, pickup.kind));
+                    return false;
+                }
+                true
+            });
+        }
+        for (ship, kind) in collected {
+            self.collect(ship, kind);
+        }
+
+        // Check for collision between the ships and the asteroids, a ship that was hit is destroyed unless its shield
+        // absorbs the asteroid, a ship that was almost hit gets a near miss. No shield holds against a gravity well.
+        let mut absorbed = Vec::new();
+        for (ship, character) in self.characters.iter_mut().enumerate() {
+            if character.destroyed {
+                continue;
+            }
+            character.shield = character.shield.saturating_sub(1);
+            if let Some(well) = self
+                .arena
+                .well_at(character.coordinate.x, character.coordinate.y)
+            {
+                character.destroyed = true;
+                let cause = match well.kind {
+                    WellKind::Planetoid => DeathCause::Planetoid,
+                    WellKind::BlackHole => DeathCause::BlackHole,
+                };
+                let (x, y) = (character.coordinate.x, character.coordinate.y);
+                self.events.push(WorldEvent::Destroyed { ship, cause, x, y });
+                continue;
+            }
+            if let Some(hit) = self
+                .asteroids
+                .iter_mut()
+                .position(|asteroid| asteroid.check_collision(character))
+            {
+                let (x, y) = (character.coordinate.x, character.coordinate.y);
+                self.events.push(WorldEvent::Collided {
+                    ship,
+                    asteroid: self.asteroids[hit].id,
+                    x,
+                    y,
+                });
+                // Getting hit breaks the combo, even when the shield holds
+                character.combo = 1;
+                character.combo_timer = 0;
+                if character.shield > 0 {
+                    character.shield = 0;
+                    absorbed.push(hit);
+                    self.events.push(WorldEvent::ShieldBroken { ship });
+                    continue;
+                }
+                character.destroyed = true;
+                self.events.push(WorldEvent::Destroyed {
+                    ship,
+                    cause: DeathCause::Asteroid,
+                    x,
+                    y,
+                });
+                continue;
+            }
+            for asteroid in self.asteroids.iter_mut() {
+                if asteroid.check_near_miss(ship, character, NEAR_MISS_MARGIN) {
+                    grazes.push((ship, asteroid.id, asteroid.coordinate.x, asteroid.coordinate.y));
+                }
+            }
+        }
+        for (ship, asteroid, x, y) in grazes {
+            if self.characters.get(ship).is_some_and(|character| !character.destroyed) {
+                self.graze(ship, asteroid, x, y);
+            }
+        }
+
+        self.clear_asteroids(|index, _| absorbed.contains(&index));
+        self.identify();
+
+        // The combo multipliers drop by one once their time is up, and hold again for a while until they are back to 1
+        for character in self.characters.iter_mut().filter(|character| character.combo > 1) {
+            character.combo_timer = character.combo_timer.saturating_sub(1);
+            if character.combo_timer == 0 {
+                character.combo -= 1;
+                character.combo_timer = COMBO_TICKS;
+            }
+        }
+
+        self.tick += 1;
+        self.arena.wind = self.arena.environment.wind.at(self.tick);
+    }
+
+    /// The add_score function adds the given points to the score, multiplied by the given combo multiplier, and returns
+    /// the points actually scored.
+    fn add_score(&mut self, points: usize, combo: usize) -> usize {
+        let points = points * combo;
+        self.score += points;
+        self.events.push(WorldEvent::Scored {
+            score: self.score,
+            progress: self.progress,
+        });
+        points
+    }
+
+    /// The graze function scores the near miss of the ship at the given index by the given asteroid, at the ship's
+    /// multiplier, then raises the ship's combo multiplier and restarts its timer. Near misses don't count towards the
+    /// progress.
+    fn graze(&mut self, ship: usize, asteroid: u64, x: f32, y: f32) {
+        let points = self.add_score(NEAR_MISS_POINTS, self.characters[ship].combo);
+        let character = &mut self.characters[ship];
+        character.combo = (character.combo + 1).min(MAX_COMBO);
+        character.combo_timer = COMBO_TICKS;
+        self.events.push(WorldEvent::NearMiss {
+            ship,
+            asteroid,
+            x,
+            y,
+            points,
+            combo: character.combo,
+        });
+    }
+
+    /// The shoot function moves the projectiles and damages the asteroids they hit. An asteroid without hit points
+    /// left is destroyed, scoring a point for the ship which fired, and breaks into fragments.
+    fn shoot(&mut self) {
+        self.projectiles.retain_mut(|projectile| projectile.update());
+
+        let asteroids = &mut self.asteroids;
+        let mut killed = Vec::new();
+        self.projectiles.retain(|projectile| {
+            let Some(hit) = asteroids
+                .iter()
+                .position(|asteroid| asteroid.hit_points > 0 && projectile.check_hit(asteroid))
+            else {
+                return true;
+            };
+            asteroids[hit].hit_points -= 1;
+            if asteroids[hit].hit_points == 0 {
+                killed.push((hit, projectile.ship));
+            }
+            false
+        });
+        if killed.is_empty() {
+            return;
+        }
+
+        let fragments: Vec<Asteroid> = killed
+            .iter()
+            .flat_map(|(hit, _)| weapon::fragment(&self.asteroids[*hit]))
+            .collect();
+        for (_, ship) in killed.iter() {
+            self.events.push(WorldEvent::Killed { ship: *ship });
+            let combo = self.characters.get(*ship).map_or(1, |character| character.combo);
+            self.progress += 1;
+            self.add_score(1, combo);
+        }
+        self.clear_asteroids(|index, _| killed.iter().any(|(hit, _)| *hit == index));
+        self.asteroids.extend(fragments);
+    }
+
+    /// The collect function applies the effect of a pickup caught by the ship at the given index.
+    fn collect(&mut self, ship: usize, kind: PickupKind) {
+        self.events.push(WorldEvent::PickedUp { ship, kind });
+        match kind {
+            PickupKind::Shield => self.characters[ship].shield = SHIELD_DURATION,
+            PickupKind::SlowTime => self.slow_time = SLOW_TIME_DURATION,
+            PickupKind::Shockwave => {
+                let center = self.characters[ship].coordinate;
+                self.clear_asteroids(|_, asteroid| {
+                    let x = asteroid.coordinate.x - center.x;
+                    let y = asteroid.coordinate.y - center.y;
+                    (x * x + y * y).sqrt() < SHOCKWAVE_RADIUS
+                });
+            }
+        }
+    }
+
+    /// The identify function gives an id to the asteroids which entered the world since the last call, the ones
+    /// whose id is still 0, and reports them as spawned. The ids follow each other from 1, a random asteroid sent back
+    /// to the top of the screen is a new asteroid and gets a new id.
+    fn identify(&mut self) {
+        for asteroid in self.asteroids.iter_mut().filter(|asteroid| asteroid.id == 0) {
+            self.next_id += 1;
+            asteroid.id = self.next_id;
+            self.events.push(WorldEvent::Spawned {
+                asteroid: asteroid.id,
+                x: asteroid.coordinate.x,
+                y: asteroid.coordinate.y,
+            });
+        }
+    }
+
+    /// The clear_asteroids function gets rid of the asteroids matching the predicate, which is given the index of each
+    /// asteroid. Cleared asteroids don't count as avoided: the level's asteroids are removed, the random ones are sent
+    /// back to the top so that their number still follows the score.
+    fn clear_asteroids(&mut self, predicate: impl Fn(usize, &Asteroid) -> bool) {
+        let arena = &self.arena;
+        let random = &mut self.random;
+        let mut index = 0;
+        self.asteroids.retain_mut(|asteroid| {
+            let cleared = predicate(index, asteroid);
+            index += 1;
+            if !cleared {
+                return true;
+            }
+            if asteroid.scripted {
+                return false;
+            }
+            let starting_point = random.range(1.0, arena.width as f32 - 1.0);
+            *asteroid = Asteroid::new(starting_point, 0.0, random);
+            true
+        });
+    }
+
+    /// The is_over function returns true once every ship in the world has been destroyed.
+    pub fn is_over(&self) -> bool {
+        self.characters.iter().all(|character| character.destroyed)
+    }
+
+    /// The leader function returns the flying ship furthest along the pad sequence, the first one on a tie, or None once
+    /// every ship has been destroyed.
+    pub fn leader(&self) -> Option<&Character> {
+        self.characters
+            .iter()
+            .filter(|character| !character.destroyed)
+            .reduce(|leader, character| if character.target > leader.target { character } else { leader })
+    }
+
+    /// The is_won function returns true once the level's win condition is met by a ship that is still flying.
+    pub fn is_won(&self) -> bool {
+        if self.is_over() {
+            return false;
+        }
+        match self.win {
+            WinCondition::Endless => false,
+            WinCondition::Survive { ticks } => self.tick >= ticks,
+            WinCondition::Score { points } => self.score >= points,
+            WinCondition::LandOnEveryPad => self.visited.iter().all(|visited| *visited),
+            WinCondition::PadSequence => self
+                .leader()
+                .is_some_and(|leader| leader.target >= self.arena.landing_pads.len()),
+        }
+    }
+
+    /// The render function draws the ground, the gravity wells, the ships that are still flying, the asteroids, the
+    /// pickups, the projectiles, the landing pads and the wind indicator, in the colors of the given theme and the
+    /// language of the given catalog.
+    pub fn render(&mut self, ctx: &mut BTerm, theme: &Theme, catalog: &Catalog) {
+        self.arena.terrain.render(ctx, self.arena.height, theme);
+        environment::render_wind(ctx, self.arena.wind, self.arena.width, theme, catalog);
+        self.arena.wells.iter().for_each(|well| well.render(ctx, theme));
+
+        self.characters
+            .iter_mut()
+            .filter(|character| !character.destroyed)
+            .for_each(|character| character.render(ctx, theme));
+
+        self.asteroids
+            .iter_mut()
+            .for_each(|asteroid| asteroid.render(ctx, theme));
+
+        self.pickups.iter().for_each(|pickup| pickup.render(ctx, theme));
+
+        self.projectiles
+            .iter()
+            .for_each(|projectile| projectile.render(ctx, theme));
+
+        // In a pad sequence the lit pad and the pads already reached get their own colors
+        let sequence = self.win == WinCondition::PadSequence;
+        let target = self.leader().map_or(0, |leader| leader.target);
+        for (index, landing_pad) in self.arena.landing_pads.iter_mut().enumerate() {
+            let color = match index.cmp(&target) {
+                Ordering::Less if sequence => theme.pad_reached,
+                Ordering::Equal if sequence => theme.pad_lit,
+                _ => theme.pad,
+            };
+            landing_pad.render_color(ctx, color);
+        }
+    }
+
+    /// The keep_in_bounds function, this is used to keep the character's ship
+    /// inside the window generated by Btermbuilder.
+    pub fn keep_in_bounds(coordinate: f32, min: i32, max: i32) -> f32 {
+        if coordinate > max as f32 {
+            return max as f32;
+        }
+
+        if coordinate < min as f32 {
+            return min as f32;
+        }
+        coordinate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::PadDefinition;
+    use crate::map::PadMotion;
+
+    /// The quiet_level function returns a level without random asteroids or pickups, so that only what a test puts in
+    /// the world happens.
+    fn quiet_level() -> Level {
+        Level {
+            random_asteroids: false,
+            pickups: false,
+            ..Level::default()
+        }
+    }
+
+    /// The weightless function returns a world of the given level without gravity nor drag, its ships stay where
+    /// they are put.
+    fn weightless(level: &Level, players: usize) -> World {
+        let mut world = World::from_level(level, players, RandomNumberGenerator::seeded(1));
+        world.arena.environment.gravity = Vector::default();
+        world.arena.environment.drag = 0.0;
+        world
+    }
+
+    /// The State type is the tick, the score, the ships and the asteroids of a world, see state.
+    type State = (u64, usize, Vec<(f32, f32, bool)>, Vec<(u64, f32, f32)>);
+
+    /// The state function sums up the world, to compare two of them.
+    fn state(world: &World) -> State {
+        (
+            world.tick,
+            world.score,
+            world
+                .characters
+                .iter()
+                .map(|character| (character.coordinate.x, character.coordinate.y, character.destroyed))
+                .collect(),
+            world
+                .asteroids
+                .iter()
+                .map(|asteroid| (asteroid.id, asteroid.coordinate.x, asteroid.coordinate.y))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn seeded_worlds_play_out_the_same() {
+        let mut first = World::seeded(2, 42);
+        let mut second = World::seeded(2, 42);
+        for tick in 0..1_000u32 {
+            let inputs = [
+                ShipInput {
+                    up: tick % 3 == 0,
+                    left: tick % 50 < 10,
+                    ..ShipInput::default()
+                },
+                ShipInput {
+                    right: tick % 7 == 0,
+                    ..ShipInput::default()
+                },
+            ];
+            first.step(&inputs);
+            second.step(&inputs);
+            assert_eq!(first.events, second.events, "tick {}", tick);
+            assert_eq!(state(&first), state(&second), "tick {}", tick);
+        }
+        assert_eq!(first.tick, 1_000);
+    }
+
+    #[test]
+    fn step_is_fly_then_advance() {
+        let inputs = [ShipInput {
+            up: true,
+            ..ShipInput::default()
+        }];
+        let mut stepped = World::seeded(1, 7);
+        let mut flown = World::seeded(1, 7);
+        for _ in 0..100 {
+            stepped.step(&inputs);
+            flown.events.clear();
+            flown.fly(0, &inputs[0]);
+            flown.advance();
+        }
+        assert_eq!(state(&stepped), state(&flown));
+    }
+
+    #[test]
+    fn passing_close_is_a_near_miss() {
+        let mut world = weightless(&quiet_level(), 1);
+        world.characters[0].coordinate = PointF::new(40.0, 20.0);
+        world.asteroids.push(Asteroid::scripted(30.0, 18.0, 0.0, 1.0, 2.0));
+        let mut near_misses = 0;
+        for _ in 0..20 {
+            world.step(&[]);
+            near_misses += world
+                .events
+                .iter()
+                .filter(|event| matches!(event, WorldEvent::NearMiss { ship: 0, points: 1, combo: 2, .. }))
+                .count();
+        }
+        assert_eq!(near_misses, 1);
+        assert_eq!(world.score, NEAR_MISS_POINTS);
+        assert_eq!(world.characters[0].combo, 2);
+        assert!(!world.characters[0].destroyed);
+    }
+
+    #[test]
+    fn a_hit_is_not_a_near_miss() {
+        let mut world = weightless(&quiet_level(), 1);
+        world.characters[0].coordinate = PointF::new(40.0, 20.0);
+        world.asteroids.push(Asteroid::scripted(30.0, 20.0, 0.0, 1.0, 2.0));
+        for _ in 0..20 {
+            world.step(&[]);
+            assert!(!world.events.iter().any(|event| matches!(event, WorldEvent::NearMiss { .. })));
+        }
+        assert!(world.characters[0].destroyed);
+        assert!(world.is_over());
+        assert_eq!(world.score, 0);
+    }
+
+    #[test]
+    fn combo_drops_back_over_time() {
+        let mut world = weightless(&quiet_level(), 1);
+        world.characters[0].combo = 3;
+        world.characters[0].combo_timer = 1;
+        world.step(&[]);
+        assert_eq!(world.characters[0].combo, 2);
+        for _ in 0..COMBO_TICKS {
+            world.step(&[]);
+        }
+        assert_eq!(world.characters[0].combo, 1);
+    }
+
+    #[test]
+    fn surviving_wins() {
+        let level = Level {
+            win: WinCondition::Survive { ticks: 10 },
+            ..quiet_level()
+        };
+        let mut world = World::from_level(&level, 1, RandomNumberGenerator::seeded(1));
+        for _ in 0..9 {
+            world.step(&[]);
+            assert!(!world.is_won());
+        }
+        world.step(&[]);
+        assert!(world.is_won());
+    }
+
+    #[test]
+    fn pad_sequences_are_tracked_per_ship() {
+        let level = Level {
+            pads: vec![
+                PadDefinition {
+                    x: 20,
+                    heigth: 10,
+                    width: 5,
+                    motion: PadMotion::Static,
+                },
+                PadDefinition {
+                    x: 60,
+                    heigth: 10,
+                    width: 5,
+                    motion: PadMotion::Static,
+                },
+            ],
+            win: WinCondition::PadSequence,
+            ..quiet_level()
+        };
+        let mut world = World::from_level(&level, 2, RandomNumberGenerator::seeded(1));
+        let height = world.arena.height as f32;
+        world.characters[0].coordinate = PointF::new(20.0, height - 10.0);
+        world.characters[1].coordinate = PointF::new(60.0, height - 10.0);
+        for _ in 0..SETTLE_TICKS {
+            world.step(&[]);
+        }
+
+        // The second ship rests on the second pad, which isn't its target yet
+        assert_eq!(world.characters[0].target, 1);
+        assert_eq!(world.characters[0].splits, vec![SETTLE_TICKS]);
+        assert_eq!(world.characters[1].target, 0);
+        assert_eq!(world.characters[1].contact, 0);
+        assert!(world.characters[1].splits.is_empty());
+
+        world.characters[0].coordinate = PointF::new(60.0, height - 10.0);
+        for _ in 0..SETTLE_TICKS {
+            assert!(!world.is_won());
+            world.step(&[]);
+        }
+        assert_eq!(world.characters[0].target, 2);
+        assert_eq!(world.leader().map(|leader| leader.target), Some(2));
+        assert!(world.is_won());
+    }
+
+    #[test]
+    fn keep_in_bounds_clamps() {
+        assert_eq!(World::keep_in_bounds(-3.0, 0, 79), 0.0);
+        assert_eq!(World::keep_in_bounds(12.5, 0, 79), 12.5);
+        assert_eq!(World::keep_in_bounds(90.0, 0, 79), 79.0);
+    }
+}